@@ -1,9 +1,11 @@
+mod tool;
+
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
 use syn::{
-    Attribute, Data, DeriveInput, Field, Fields, GenericArgument, Ident, PathArguments, Type,
-    parse_macro_input,
+    Attribute, Data, DeriveInput, Field, Fields, GenericArgument, Ident, ItemFn, PathArguments,
+    Type, parse_macro_input,
 };
 
 /// Derive macro that generates UniFFI-compatible wrapper types from wasmtime bindgen output.
@@ -19,6 +21,30 @@ pub fn export_derive(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Attribute macro that turns a plain `fn` or `async fn` into a `strands::Tool`.
+///
+/// The function is replaced by a unit struct of the same name, so it can be
+/// registered with `AgentBuilder::with_tool(my_fn)`. The description comes from
+/// the doc comment, property descriptions from doc comments on the arguments, and
+/// the input schema from the argument types. Returning `Result<T, E>` reports
+/// `Err` to the model as a tool error. An argument whose type is written
+/// `ToolContext` or `strands::ToolContext` receives the context of the call and
+/// is left out of the schema; the type is matched by name, so a renamed import
+/// is treated as an ordinary argument.
+///
+/// Accepts optional `name = "..."` and `description = "..."` overrides.
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = tool::ToolArgs::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
+    let func = parse_macro_input!(item as ItemFn);
+    match tool::expand(args, func) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 fn export_derive_inner(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let kind = match get_meta(&input.attrs, &["enum", "record", "variant"])? {
         Some(k) => k,
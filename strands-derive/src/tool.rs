@@ -0,0 +1,557 @@
+//! Expansion for the `#[tool]` attribute macro.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Expr, ExprLit, FnArg, GenericArgument, ItemFn, Lit, LitStr, Meta, Pat,
+    PathArguments, ReturnType, Type, TypePath,
+};
+
+/// Optional `name = "..."` and `description = "..."` overrides.
+#[derive(Default)]
+pub struct ToolArgs {
+    name: Option<LitStr>,
+    description: Option<LitStr>,
+}
+
+impl ToolArgs {
+    pub fn parse(&mut self, meta: syn::meta::ParseNestedMeta<'_>) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("description") {
+            self.description = Some(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error("expected `name` or `description`"));
+        }
+        Ok(())
+    }
+}
+
+pub fn expand(args: ToolArgs, mut func: ItemFn) -> syn::Result<TokenStream2> {
+    if !func.sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &func.sig.generics,
+            "tool functions cannot be generic",
+        ));
+    }
+
+    let vis = std::mem::replace(&mut func.vis, syn::Visibility::Inherited);
+    let ident = func.sig.ident.clone();
+    let input_ident = format_ident!("__{}Input", camel_case(&ident.to_string()));
+    let tool_name = args
+        .name
+        .map_or_else(|| ident.to_string(), |name| name.value());
+    let description = match args.description {
+        Some(desc) => desc.value(),
+        None => doc_string(&func.attrs).ok_or_else(|| {
+            syn::Error::new_spanned(
+                &func.sig.ident,
+                "tool functions need a doc comment or a `description = \"...\"` argument",
+            )
+        })?,
+    };
+
+    let mut fields = Vec::new();
+    let mut names = Vec::new();
+    let mut args = Vec::new();
+    let mut context = None;
+    for arg in &mut func.sig.inputs {
+        let FnArg::Typed(arg) = arg else {
+            return Err(syn::Error::new_spanned(
+                arg,
+                "tool functions cannot take `self`",
+            ));
+        };
+        let Pat::Ident(pat) = &*arg.pat else {
+            return Err(syn::Error::new_spanned(
+                &arg.pat,
+                "tool arguments must be plain identifiers",
+            ));
+        };
+        if matches!(*arg.ty, Type::Reference(_)) {
+            return Err(syn::Error::new_spanned(
+                &arg.ty,
+                "tool arguments must be owned types",
+            ));
+        }
+
+        // Parameter docs become field docs on the input struct, where schemars
+        // picks them up as property descriptions. They are stripped from the
+        // function itself since rustc rejects doc comments on parameters.
+        let (docs, rest): (Vec<Attribute>, Vec<Attribute>) = arg
+            .attrs
+            .drain(..)
+            .partition(|attr| attr.path().is_ident("doc"));
+        arg.attrs = rest;
+
+        let name = &pat.ident;
+        args.push(name.clone());
+        if is_tool_context(&arg.ty) {
+            if context.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &arg.ty,
+                    "tool functions take at most one `ToolContext`",
+                ));
+            }
+            context = Some(name.clone());
+            continue;
+        }
+        let ty = &arg.ty;
+        fields.push(quote!(#(#docs)* #name: #ty));
+        names.push(name.clone());
+    }
+
+    let wait = func.sig.asyncness.map(|_| quote!(.await));
+    let (output, call) = match &func.sig.output {
+        ReturnType::Default => (quote!(()), quote!(Ok(__inner(#(#args),*)#wait))),
+        ReturnType::Type(_, ty) => match result_ok_type(ty) {
+            Some(ok) => (
+                quote!(#ok),
+                quote!(__inner(#(#args),*)#wait.map_err(|e| e.to_string())),
+            ),
+            None => (quote!(#ty), quote!(Ok(__inner(#(#args),*)#wait))),
+        },
+    };
+
+    func.attrs.retain(|attr| !attr.path().is_ident("doc"));
+    func.sig.ident = format_ident!("__inner");
+
+    // A function taking a `ToolContext` runs only as part of an agent's tool
+    // call, through `call_with_context`.
+    let methods = match &context {
+        None => quote! {
+            async fn call(&self, input: Self::Input) -> ::std::result::Result<Self::Output, String> {
+                #func
+
+                let #input_ident { #(#names),* } = input;
+                #call
+            }
+        },
+        Some(context) => quote! {
+            async fn call(&self, _input: Self::Input) -> ::std::result::Result<Self::Output, String> {
+                Err(format!("tool '{}' needs the context of a tool call", #tool_name))
+            }
+
+            async fn call_with_context(
+                &self,
+                input: Self::Input,
+                #context: ::strands::ToolContext,
+            ) -> ::std::result::Result<Self::Output, String> {
+                #func
+
+                let #input_ident { #(#names),* } = input;
+                #call
+            }
+        },
+    };
+
+    Ok(quote! {
+        #[doc = #description]
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, Default)]
+        #vis struct #ident;
+
+        const _: () = {
+            #[derive(::strands::__private::serde::Deserialize, ::strands::__private::schemars::JsonSchema)]
+            #[serde(crate = "::strands::__private::serde")]
+            #[schemars(crate = "::strands::__private::schemars", title = #tool_name)]
+            #vis struct #input_ident {
+                #(#fields,)*
+            }
+
+            impl ::strands::Tool for #ident {
+                type Input = #input_ident;
+                type Output = #output;
+
+                fn name(&self) -> &str {
+                    #tool_name
+                }
+
+                fn description(&self) -> &str {
+                    #description
+                }
+
+                #methods
+            }
+        };
+    })
+}
+
+/// Join `///` lines into a single description, trimming the leading space rustdoc adds.
+fn doc_string(attrs: &[Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() { None } else { Some(doc) }
+}
+
+/// Whether `ty` is written `ToolContext`, `strands::ToolContext` or
+/// `::strands::ToolContext`. Macros cannot resolve imports, so a renamed
+/// import is not recognized, and any other type in scope as `ToolContext`
+/// is taken for it.
+fn is_tool_context(ty: &Type) -> bool {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return false;
+    };
+    let idents: Option<Vec<String>> = path
+        .segments
+        .iter()
+        .map(|segment| {
+            segment
+                .arguments
+                .is_none()
+                .then(|| segment.ident.to_string())
+        })
+        .collect();
+    match idents.as_deref() {
+        Some([name]) => path.leading_colon.is_none() && name == "ToolContext",
+        Some([krate, name]) => krate == "strands" && name == "ToolContext",
+        _ => false,
+    }
+}
+
+/// The `T` in `Result<T, E>` (or an alias like `anyhow::Result<T>`).
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+    if let Type::Path(p) = ty
+        && let Some(s) = p.path.segments.last()
+        && s.ident == "Result"
+        && let PathArguments::AngleBracketed(args) = &s.arguments
+        && let Some(GenericArgument::Type(t)) = args.args.first()
+    {
+        return Some(t);
+    }
+
+    None
+}
+
+fn camel_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse::Parser;
+    use syn::{ImplItem, Item, ItemImpl, ItemStruct, parse_quote};
+
+    use super::*;
+
+    /// The parts of a successful expansion.
+    struct Expanded {
+        tool: ItemStruct,
+        input: ItemStruct,
+        tool_impl: ItemImpl,
+    }
+
+    impl Expanded {
+        fn method(&self, name: &str) -> Option<String> {
+            self.tool_impl.items.iter().find_map(|item| match item {
+                ImplItem::Fn(f) if f.sig.ident == name => Some(quote!(#f).to_string()),
+                _ => None,
+            })
+        }
+
+        fn output(&self) -> String {
+            self.tool_impl
+                .items
+                .iter()
+                .find_map(|item| match item {
+                    ImplItem::Type(t) if t.ident == "Output" => Some(t.ty.clone()),
+                    _ => None,
+                })
+                .map(|ty| quote!(#ty).to_string())
+                .unwrap()
+        }
+
+        fn fields(&self) -> Vec<(String, Option<String>)> {
+            self.input
+                .fields
+                .iter()
+                .map(|field| {
+                    let name = field.ident.as_ref().unwrap().to_string();
+                    (name, doc_string(&field.attrs))
+                })
+                .collect()
+        }
+    }
+
+    fn try_expand(args: TokenStream2, func: ItemFn) -> syn::Result<Expanded> {
+        let mut parsed = ToolArgs::default();
+        syn::meta::parser(|meta| parsed.parse(meta)).parse2(args)?;
+        let file: syn::File = syn::parse2(expand(parsed, func)?).unwrap();
+        let [Item::Struct(tool), Item::Const(block)] = &file.items[..] else {
+            panic!("unexpected expansion: {}", quote!(#file));
+        };
+        let Expr::Block(block) = &*block.expr else {
+            panic!("expected a const block");
+        };
+        let mut input = None;
+        let mut tool_impl = None;
+        for stmt in &block.block.stmts {
+            match stmt {
+                syn::Stmt::Item(Item::Struct(s)) => input = Some(s.clone()),
+                syn::Stmt::Item(Item::Impl(i)) => tool_impl = Some(i.clone()),
+                _ => {}
+            }
+        }
+        Ok(Expanded {
+            tool: tool.clone(),
+            input: input.unwrap(),
+            tool_impl: tool_impl.unwrap(),
+        })
+    }
+
+    fn expanded(args: TokenStream2, func: ItemFn) -> Expanded {
+        try_expand(args, func).unwrap_or_else(|e| panic!("expansion failed: {e}"))
+    }
+
+    fn error(args: TokenStream2, func: ItemFn) -> String {
+        match try_expand(args, func) {
+            Ok(_) => panic!("expected the expansion to fail"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn title(input: &ItemStruct) -> String {
+        let schemars = input
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("schemars"))
+            .unwrap();
+        quote!(#schemars).to_string()
+    }
+
+    #[test]
+    fn docs_become_the_description_and_schema() {
+        let tool = expanded(
+            quote!(),
+            parse_quote! {
+                /// Add two numbers.
+                ///
+                /// Overflow wraps.
+                pub fn add(
+                    /// The left operand.
+                    a: i64,
+                    b: i64,
+                ) -> i64 {
+                    a.wrapping_add(b)
+                }
+            },
+        );
+        let description = "Add two numbers.\n\nOverflow wraps.";
+        assert_eq!(tool.tool.ident, "add");
+        assert!(matches!(tool.tool.vis, syn::Visibility::Public(_)));
+        assert_eq!(doc_string(&tool.tool.attrs).as_deref(), Some(description));
+        assert!(
+            tool.method("description")
+                .unwrap()
+                .contains(&format!("{description:?}"))
+        );
+        assert_eq!(tool.input.ident, "__AddInput");
+        assert_eq!(
+            tool.fields(),
+            [
+                ("a".to_string(), Some("The left operand.".to_string())),
+                ("b".to_string(), None),
+            ]
+        );
+        assert!(title(&tool.input).contains(r#"title = "add""#));
+        // The original function keeps no doc comments, on itself or its
+        // arguments.
+        let call = tool.method("call").unwrap();
+        assert!(call.contains("fn __inner (a : i64 , b : i64 ,)"));
+        assert!(!call.contains("doc"));
+    }
+
+    #[test]
+    fn overrides_the_name_and_description() {
+        let tool = expanded(
+            quote!(name = "plus", description = "Adds."),
+            parse_quote! {
+                /// Ignored.
+                fn add(a: i64) -> i64 { a }
+            },
+        );
+        assert!(tool.method("name").unwrap().contains(r#""plus""#));
+        assert!(title(&tool.input).contains(r#"title = "plus""#));
+        assert!(tool.method("description").unwrap().contains(r#""Adds.""#));
+        assert_eq!(doc_string(&tool.tool.attrs).as_deref(), Some("Adds."));
+
+        let undocumented = error(
+            quote!(),
+            parse_quote!(
+                fn add(a: i64) -> i64 {
+                    a
+                }
+            ),
+        );
+        assert!(undocumented.contains("need a doc comment"));
+        let unknown = error(
+            quote!(title = "x"),
+            parse_quote!(
+                fn add() {}
+            ),
+        );
+        assert!(unknown.contains("expected `name` or `description`"));
+    }
+
+    #[test]
+    fn unwraps_result_returns() {
+        let fallible = expanded(
+            quote!(description = "d"),
+            parse_quote!(
+                fn f() -> Result<u8, std::io::Error> {
+                    Ok(1)
+                }
+            ),
+        );
+        assert_eq!(fallible.output(), "u8");
+        assert!(fallible.method("call").unwrap().contains("map_err"));
+
+        let aliased = expanded(
+            quote!(description = "d"),
+            parse_quote!(
+                async fn f() -> anyhow::Result<String> {
+                    Ok(String::new())
+                }
+            ),
+        );
+        assert_eq!(aliased.output(), "String");
+        assert!(
+            aliased
+                .method("call")
+                .unwrap()
+                .contains("__inner () . await . map_err")
+        );
+
+        let plain = expanded(
+            quote!(description = "d"),
+            parse_quote!(
+                fn f() -> Vec<u8> {
+                    vec![]
+                }
+            ),
+        );
+        assert_eq!(plain.output(), "Vec < u8 >");
+        assert!(plain.method("call").unwrap().contains("Ok (__inner ())"));
+
+        let unit = expanded(
+            quote!(description = "d"),
+            parse_quote!(
+                fn f() {}
+            ),
+        );
+        assert_eq!(unit.output(), "()");
+    }
+
+    #[test]
+    fn passes_the_tool_context_outside_the_schema() {
+        for context in [
+            quote!(ToolContext),
+            quote!(strands::ToolContext),
+            quote!(::strands::ToolContext),
+        ] {
+            let tool = expanded(
+                quote!(description = "d"),
+                parse_quote!(fn f(ctx: #context, a: u8) -> u8 { a }),
+            );
+            assert_eq!(tool.fields(), [("a".to_string(), None)]);
+            let with_context = tool.method("call_with_context").unwrap();
+            assert!(with_context.contains("ctx : :: strands :: ToolContext"));
+            assert!(with_context.contains("__inner (ctx , a)"));
+            assert!(tool.method("call").unwrap().contains("needs the context"));
+        }
+
+        let plain = expanded(
+            quote!(description = "d"),
+            parse_quote!(
+                fn f(a: u8) -> u8 {
+                    a
+                }
+            ),
+        );
+        assert!(plain.method("call_with_context").is_none());
+
+        let twice = error(
+            quote!(description = "d"),
+            parse_quote!(
+                fn f(a: ToolContext, b: strands::ToolContext) {}
+            ),
+        );
+        assert!(twice.contains("at most one `ToolContext`"));
+    }
+
+    #[test]
+    fn rejects_unsupported_signatures() {
+        let cases: [(ItemFn, &str); 4] = [
+            (
+                parse_quote!(
+                    fn f<T>(a: T) {}
+                ),
+                "cannot be generic",
+            ),
+            (
+                parse_quote!(
+                    fn f(&self) {}
+                ),
+                "cannot take `self`",
+            ),
+            (
+                parse_quote!(
+                    fn f(a: &str) {}
+                ),
+                "must be owned types",
+            ),
+            (
+                parse_quote!(
+                    fn f((a, b): (u8, u8)) {}
+                ),
+                "plain identifiers",
+            ),
+        ];
+        for (func, expected) in cases {
+            let message = error(quote!(description = "d"), func);
+            assert!(message.contains(expected), "{message}");
+        }
+    }
+
+    #[test]
+    fn recognizes_the_tool_context_by_path() {
+        let is = |ty: Type| is_tool_context(&ty);
+        assert!(is(parse_quote!(ToolContext)));
+        assert!(is(parse_quote!(strands::ToolContext)));
+        assert!(is(parse_quote!(::strands::ToolContext)));
+        assert!(!is(parse_quote!(::ToolContext)));
+        assert!(!is(parse_quote!(other::ToolContext)));
+        assert!(!is(parse_quote!(strands::context::ToolContext)));
+        assert!(!is(parse_quote!(ToolContext<u8>)));
+        assert!(!is(parse_quote!(Ctx)));
+        assert!(!is(parse_quote!(&ToolContext)));
+    }
+}
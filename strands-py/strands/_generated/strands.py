@@ -1945,16 +1945,17 @@ class _UniffiFfiConverterTypeAgentConfig_(_UniffiConverterRustBuffer):
 
 @dataclass
 class CallToolArgs:
-    def __init__(self, *, name:str, input:str, tool_use_id:str):
+    def __init__(self, *, name:str, input:str, tool_use_id:str, agent_state:typing.Optional[str]):
         self.name = name
         self.input = input
         self.tool_use_id = tool_use_id
+        self.agent_state = agent_state
         
         
 
     
     def __str__(self):
-        return "CallToolArgs(name={}, input={}, tool_use_id={})".format(self.name, self.input, self.tool_use_id)
+        return "CallToolArgs(name={}, input={}, tool_use_id={}, agent_state={})".format(self.name, self.input, self.tool_use_id, self.agent_state)
     def __eq__(self, other):
         if self.name != other.name:
             return False
@@ -1962,6 +1963,8 @@ class CallToolArgs:
             return False
         if self.tool_use_id != other.tool_use_id:
             return False
+        if self.agent_state != other.agent_state:
+            return False
         return True
 
 class _UniffiFfiConverterTypeCallToolArgs_(_UniffiConverterRustBuffer):
@@ -1971,6 +1974,7 @@ class _UniffiFfiConverterTypeCallToolArgs_(_UniffiConverterRustBuffer):
             name=_UniffiFfiConverterString.read(buf),
             input=_UniffiFfiConverterString.read(buf),
             tool_use_id=_UniffiFfiConverterString.read(buf),
+            agent_state=_UniffiFfiConverterOptionalString.read(buf),
         )
 
     @staticmethod
@@ -1978,12 +1982,14 @@ class _UniffiFfiConverterTypeCallToolArgs_(_UniffiConverterRustBuffer):
         _UniffiFfiConverterString.check_lower(value.name)
         _UniffiFfiConverterString.check_lower(value.input)
         _UniffiFfiConverterString.check_lower(value.tool_use_id)
+        _UniffiFfiConverterOptionalString.check_lower(value.agent_state)
 
     @staticmethod
     def write(value, buf):
         _UniffiFfiConverterString.write(value.name, buf)
         _UniffiFfiConverterString.write(value.input, buf)
         _UniffiFfiConverterString.write(value.tool_use_id, buf)
+        _UniffiFfiConverterOptionalString.write(value.agent_state, buf)
 
 class _UniffiFfiConverterSequenceTypeCallToolArgs_(_UniffiConverterRustBuffer):
     @classmethod
@@ -2038,6 +2044,54 @@ class _UniffiFfiConverterTypeCallToolsArgs_(_UniffiConverterRustBuffer):
     def write(value, buf):
         _UniffiFfiConverterSequenceTypeCallToolArgs_.write(value.calls, buf)
 
+@dataclass
+class HookAction:
+    def __init__(self, *, kind:str, cancel:typing.Optional[str], replace:typing.Optional[str], interrupt:typing.Optional[str]):
+        self.kind = kind
+        self.cancel = cancel
+        self.replace = replace
+        self.interrupt = interrupt
+        
+        
+
+    
+    def __str__(self):
+        return "HookAction(kind={}, cancel={}, replace={}, interrupt={})".format(self.kind, self.cancel, self.replace, self.interrupt)
+    def __eq__(self, other):
+        if self.kind != other.kind:
+            return False
+        if self.cancel != other.cancel:
+            return False
+        if self.replace != other.replace:
+            return False
+        if self.interrupt != other.interrupt:
+            return False
+        return True
+
+class _UniffiFfiConverterTypeHookAction_(_UniffiConverterRustBuffer):
+    @staticmethod
+    def read(buf):
+        return HookAction(
+            kind=_UniffiFfiConverterString.read(buf),
+            cancel=_UniffiFfiConverterOptionalString.read(buf),
+            replace=_UniffiFfiConverterOptionalString.read(buf),
+            interrupt=_UniffiFfiConverterOptionalString.read(buf),
+        )
+
+    @staticmethod
+    def check_lower(value):
+        _UniffiFfiConverterString.check_lower(value.kind)
+        _UniffiFfiConverterOptionalString.check_lower(value.cancel)
+        _UniffiFfiConverterOptionalString.check_lower(value.replace)
+        _UniffiFfiConverterOptionalString.check_lower(value.interrupt)
+
+    @staticmethod
+    def write(value, buf):
+        _UniffiFfiConverterString.write(value.kind, buf)
+        _UniffiFfiConverterOptionalString.write(value.cancel, buf)
+        _UniffiFfiConverterOptionalString.write(value.replace, buf)
+        _UniffiFfiConverterOptionalString.write(value.interrupt, buf)
+
 @dataclass
 class LifecycleEventType:
     def __init__(self, *, value:str):
@@ -2068,6 +2122,60 @@ class _UniffiFfiConverterTypeLifecycleEventType_(_UniffiConverterRustBuffer):
     def write(value, buf):
         _UniffiFfiConverterString.write(value.value, buf)
 
+@dataclass
+class HookEvent:
+    def __init__(self, *, event_type:LifecycleEventType, tool_use:typing.Optional[str], tool_result:typing.Optional[str], message:typing.Optional[str], interrupt_response:typing.Optional[str]):
+        self.event_type = event_type
+        self.tool_use = tool_use
+        self.tool_result = tool_result
+        self.message = message
+        self.interrupt_response = interrupt_response
+        
+        
+
+    
+    def __str__(self):
+        return "HookEvent(event_type={}, tool_use={}, tool_result={}, message={}, interrupt_response={})".format(self.event_type, self.tool_use, self.tool_result, self.message, self.interrupt_response)
+    def __eq__(self, other):
+        if self.event_type != other.event_type:
+            return False
+        if self.tool_use != other.tool_use:
+            return False
+        if self.tool_result != other.tool_result:
+            return False
+        if self.message != other.message:
+            return False
+        if self.interrupt_response != other.interrupt_response:
+            return False
+        return True
+
+class _UniffiFfiConverterTypeHookEvent_(_UniffiConverterRustBuffer):
+    @staticmethod
+    def read(buf):
+        return HookEvent(
+            event_type=_UniffiFfiConverterTypeLifecycleEventType_.read(buf),
+            tool_use=_UniffiFfiConverterOptionalString.read(buf),
+            tool_result=_UniffiFfiConverterOptionalString.read(buf),
+            message=_UniffiFfiConverterOptionalString.read(buf),
+            interrupt_response=_UniffiFfiConverterOptionalString.read(buf),
+        )
+
+    @staticmethod
+    def check_lower(value):
+        _UniffiFfiConverterTypeLifecycleEventType_.check_lower(value.event_type)
+        _UniffiFfiConverterOptionalString.check_lower(value.tool_use)
+        _UniffiFfiConverterOptionalString.check_lower(value.tool_result)
+        _UniffiFfiConverterOptionalString.check_lower(value.message)
+        _UniffiFfiConverterOptionalString.check_lower(value.interrupt_response)
+
+    @staticmethod
+    def write(value, buf):
+        _UniffiFfiConverterTypeLifecycleEventType_.write(value.event_type, buf)
+        _UniffiFfiConverterOptionalString.write(value.tool_use, buf)
+        _UniffiFfiConverterOptionalString.write(value.tool_result, buf)
+        _UniffiFfiConverterOptionalString.write(value.message, buf)
+        _UniffiFfiConverterOptionalString.write(value.interrupt_response, buf)
+
 @dataclass
 class LifecycleEvent:
     def __init__(self, *, event_type:LifecycleEventType, tool_use:typing.Optional[str], tool_result:typing.Optional[str]):
@@ -2484,6 +2592,36 @@ class _UniffiFfiConverterTypeSetMessagesArgs_(_UniffiConverterRustBuffer):
     def write(value, buf):
         _UniffiFfiConverterString.write(value.json, buf)
 
+@dataclass
+class SetToolsArgs:
+    def __init__(self, *, tools:typing.List[ToolSpec]):
+        self.tools = tools
+        
+        
+
+    
+    def __str__(self):
+        return "SetToolsArgs(tools={})".format(self.tools)
+    def __eq__(self, other):
+        if self.tools != other.tools:
+            return False
+        return True
+
+class _UniffiFfiConverterTypeSetToolsArgs_(_UniffiConverterRustBuffer):
+    @staticmethod
+    def read(buf):
+        return SetToolsArgs(
+            tools=_UniffiFfiConverterSequenceTypeToolSpec_.read(buf),
+        )
+
+    @staticmethod
+    def check_lower(value):
+        _UniffiFfiConverterSequenceTypeToolSpec_.check_lower(value.tools)
+
+    @staticmethod
+    def write(value, buf):
+        _UniffiFfiConverterSequenceTypeToolSpec_.write(value.tools, buf)
+
 @dataclass
 class StopReason:
     def __init__(self, *, value:str):
@@ -2927,6 +3065,65 @@ class _UniffiFfiConverterTypeToolSpecConfig(_UniffiConverterRustBuffer):
         _UniffiFfiConverterString.write(value.description, buf)
         _UniffiFfiConverterString.write(value.input_schema, buf)
 
+class _UniffiFfiConverterUInt16(_UniffiConverterPrimitiveInt):
+    CLASS_NAME = "u16"
+    VALUE_MIN = 0
+    VALUE_MAX = 2**16
+
+    @staticmethod
+    def read(buf):
+        return buf.read_u16()
+
+    @staticmethod
+    def write(value, buf):
+        buf.write_u16(value)
+
+class _UniffiFfiConverterOptionalUInt16(_UniffiConverterRustBuffer):
+    @classmethod
+    def check_lower(cls, value):
+        if value is not None:
+            _UniffiFfiConverterUInt16.check_lower(value)
+
+    @classmethod
+    def write(cls, value, buf):
+        if value is None:
+            buf.write_u8(0)
+            return
+
+        buf.write_u8(1)
+        _UniffiFfiConverterUInt16.write(value, buf)
+
+    @classmethod
+    def read(cls, buf):
+        flag = buf.read_u8()
+        if flag == 0:
+            return None
+        elif flag == 1:
+            return _UniffiFfiConverterUInt16.read(buf)
+        else:
+            raise InternalError("Unexpected flag byte for optional type")
+
+class _UniffiFfiConverterBoolean:
+    @classmethod
+    def check_lower(cls, value):
+        return not not value
+
+    @classmethod
+    def lower(cls, value):
+        return 1 if value else 0
+
+    @staticmethod
+    def lift(value):
+        return value != 0
+
+    @classmethod
+    def read(cls, buf):
+        return cls.lift(buf.read_u8())
+
+    @classmethod
+    def write(cls, value, buf):
+        buf.write_u8(value)
+
 
 
 # AgentError
@@ -2955,6 +3152,125 @@ class AgentError:  # type: ignore
         def __repr__(self):
             return "AgentError.Runtime({})".format(str(self))
     _UniffiTempAgentError.Runtime = Runtime # type: ignore
+    class Trap(_UniffiTempAgentError):
+        
+        def __init__(self, reason):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+            ]))
+            self.reason = reason
+
+        def __repr__(self):
+            return "AgentError.Trap({})".format(str(self))
+    _UniffiTempAgentError.Trap = Trap # type: ignore
+    class Guest(_UniffiTempAgentError):
+        
+        def __init__(self, reason):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+            ]))
+            self.reason = reason
+
+        def __repr__(self):
+            return "AgentError.Guest({})".format(str(self))
+    _UniffiTempAgentError.Guest = Guest # type: ignore
+    class Model(_UniffiTempAgentError):
+        
+        def __init__(self, reason, status, retryable):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+                "status={!r}".format(status),
+                "retryable={!r}".format(retryable),
+            ]))
+            self.reason = reason
+            self.status = status
+            self.retryable = retryable
+
+        def __repr__(self):
+            return "AgentError.Model({})".format(str(self))
+    _UniffiTempAgentError.Model = Model # type: ignore
+    class UnknownTool(_UniffiTempAgentError):
+        
+        def __init__(self, name):
+            super().__init__(", ".join([
+                "name={!r}".format(name),
+            ]))
+            self.name = name
+
+        def __repr__(self):
+            return "AgentError.UnknownTool({})".format(str(self))
+    _UniffiTempAgentError.UnknownTool = UnknownTool # type: ignore
+    class Cancelled(_UniffiTempAgentError):
+        
+        def __init__(self):
+            pass
+
+        def __repr__(self):
+            return "AgentError.Cancelled({})".format(str(self))
+    _UniffiTempAgentError.Cancelled = Cancelled # type: ignore
+    class MissingCredentials(_UniffiTempAgentError):
+        
+        def __init__(self, reason):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+            ]))
+            self.reason = reason
+
+        def __repr__(self):
+            return "AgentError.MissingCredentials({})".format(str(self))
+    _UniffiTempAgentError.MissingCredentials = MissingCredentials # type: ignore
+    class StructuredOutput(_UniffiTempAgentError):
+        
+        def __init__(self, reason):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+            ]))
+            self.reason = reason
+
+        def __repr__(self):
+            return "AgentError.StructuredOutput({})".format(str(self))
+    _UniffiTempAgentError.StructuredOutput = StructuredOutput # type: ignore
+    class ComponentLoad(_UniffiTempAgentError):
+        
+        def __init__(self, reason):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+            ]))
+            self.reason = reason
+
+        def __repr__(self):
+            return "AgentError.ComponentLoad({})".format(str(self))
+    _UniffiTempAgentError.ComponentLoad = ComponentLoad # type: ignore
+    class Timeout(_UniffiTempAgentError):
+        
+        def __init__(self):
+            pass
+
+        def __repr__(self):
+            return "AgentError.Timeout({})".format(str(self))
+    _UniffiTempAgentError.Timeout = Timeout # type: ignore
+    class LimitExceeded(_UniffiTempAgentError):
+        
+        def __init__(self, limit):
+            super().__init__(", ".join([
+                "limit={!r}".format(limit),
+            ]))
+            self.limit = limit
+
+        def __repr__(self):
+            return "AgentError.LimitExceeded({})".format(str(self))
+    _UniffiTempAgentError.LimitExceeded = LimitExceeded # type: ignore
+    class MultiAgent(_UniffiTempAgentError):
+        
+        def __init__(self, reason):
+            super().__init__(", ".join([
+                "reason={!r}".format(reason),
+            ]))
+            self.reason = reason
+
+        def __repr__(self):
+            return "AgentError.MultiAgent({})".format(str(self))
+    _UniffiTempAgentError.MultiAgent = MultiAgent # type: ignore
 
 AgentError = _UniffiTempAgentError # type: ignore
 del _UniffiTempAgentError
@@ -2969,6 +3285,50 @@ class _UniffiFfiConverterTypeAgentError(_UniffiConverterRustBuffer):
                 _UniffiFfiConverterString.read(buf),
                 _UniffiFfiConverterOptionalString.read(buf),
             )
+        if variant == 2:
+            return AgentError.Trap(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 3:
+            return AgentError.Guest(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 4:
+            return AgentError.Model(
+                _UniffiFfiConverterString.read(buf),
+                _UniffiFfiConverterOptionalUInt16.read(buf),
+                _UniffiFfiConverterBoolean.read(buf),
+            )
+        if variant == 5:
+            return AgentError.UnknownTool(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 6:
+            return AgentError.Cancelled(
+            )
+        if variant == 7:
+            return AgentError.MissingCredentials(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 8:
+            return AgentError.StructuredOutput(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 9:
+            return AgentError.ComponentLoad(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 10:
+            return AgentError.Timeout(
+            )
+        if variant == 11:
+            return AgentError.LimitExceeded(
+                _UniffiFfiConverterString.read(buf),
+            )
+        if variant == 12:
+            return AgentError.MultiAgent(
+                _UniffiFfiConverterString.read(buf),
+            )
         raise InternalError("Raw enum value doesn't match any cases")
 
     @staticmethod
@@ -2977,6 +3337,39 @@ class _UniffiFfiConverterTypeAgentError(_UniffiConverterRustBuffer):
             _UniffiFfiConverterString.check_lower(value.reason)
             _UniffiFfiConverterOptionalString.check_lower(value.detail)
             return
+        if isinstance(value, AgentError.Trap):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            return
+        if isinstance(value, AgentError.Guest):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            return
+        if isinstance(value, AgentError.Model):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            _UniffiFfiConverterOptionalUInt16.check_lower(value.status)
+            _UniffiFfiConverterBoolean.check_lower(value.retryable)
+            return
+        if isinstance(value, AgentError.UnknownTool):
+            _UniffiFfiConverterString.check_lower(value.name)
+            return
+        if isinstance(value, AgentError.Cancelled):
+            return
+        if isinstance(value, AgentError.MissingCredentials):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            return
+        if isinstance(value, AgentError.StructuredOutput):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            return
+        if isinstance(value, AgentError.ComponentLoad):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            return
+        if isinstance(value, AgentError.Timeout):
+            return
+        if isinstance(value, AgentError.LimitExceeded):
+            _UniffiFfiConverterString.check_lower(value.limit)
+            return
+        if isinstance(value, AgentError.MultiAgent):
+            _UniffiFfiConverterString.check_lower(value.reason)
+            return
 
     @staticmethod
     def write(value, buf):
@@ -2984,6 +3377,39 @@ class _UniffiFfiConverterTypeAgentError(_UniffiConverterRustBuffer):
             buf.write_i32(1)
             _UniffiFfiConverterString.write(value.reason, buf)
             _UniffiFfiConverterOptionalString.write(value.detail, buf)
+        if isinstance(value, AgentError.Trap):
+            buf.write_i32(2)
+            _UniffiFfiConverterString.write(value.reason, buf)
+        if isinstance(value, AgentError.Guest):
+            buf.write_i32(3)
+            _UniffiFfiConverterString.write(value.reason, buf)
+        if isinstance(value, AgentError.Model):
+            buf.write_i32(4)
+            _UniffiFfiConverterString.write(value.reason, buf)
+            _UniffiFfiConverterOptionalUInt16.write(value.status, buf)
+            _UniffiFfiConverterBoolean.write(value.retryable, buf)
+        if isinstance(value, AgentError.UnknownTool):
+            buf.write_i32(5)
+            _UniffiFfiConverterString.write(value.name, buf)
+        if isinstance(value, AgentError.Cancelled):
+            buf.write_i32(6)
+        if isinstance(value, AgentError.MissingCredentials):
+            buf.write_i32(7)
+            _UniffiFfiConverterString.write(value.reason, buf)
+        if isinstance(value, AgentError.StructuredOutput):
+            buf.write_i32(8)
+            _UniffiFfiConverterString.write(value.reason, buf)
+        if isinstance(value, AgentError.ComponentLoad):
+            buf.write_i32(9)
+            _UniffiFfiConverterString.write(value.reason, buf)
+        if isinstance(value, AgentError.Timeout):
+            buf.write_i32(10)
+        if isinstance(value, AgentError.LimitExceeded):
+            buf.write_i32(11)
+            _UniffiFfiConverterString.write(value.limit, buf)
+        if isinstance(value, AgentError.MultiAgent):
+            buf.write_i32(12)
+            _UniffiFfiConverterString.write(value.reason, buf)
 
 
 class StreamHandleProtocol(typing.Protocol):
@@ -3707,27 +4133,6 @@ class _UniffiFfiConverterOptionalTypeLogHandler(_UniffiConverterRustBuffer):
         else:
             raise InternalError("Unexpected flag byte for optional type")
 
-class _UniffiFfiConverterBoolean:
-    @classmethod
-    def check_lower(cls, value):
-        return not not value
-
-    @classmethod
-    def lower(cls, value):
-        return 1 if value else 0
-
-    @staticmethod
-    def lift(value):
-        return value != 0
-
-    @classmethod
-    def read(cls, buf):
-        return cls.lift(buf.read_u8())
-
-    @classmethod
-    def write(cls, value, buf):
-        buf.write_u8(value)
-
 __all__ = [
     "InternalError",
     "AgentError",
@@ -3745,7 +4150,9 @@ __all__ = [
     "AgentConfig",
     "CallToolArgs",
     "CallToolsArgs",
+    "HookAction",
     "LifecycleEventType",
+    "HookEvent",
     "LifecycleEvent",
     "LogLevel",
     "LogEntry",
@@ -3755,6 +4162,7 @@ __all__ = [
     "ModelConfigInput",
     "RespondArgs",
     "SetMessagesArgs",
+    "SetToolsArgs",
     "StopReason",
     "StopData",
     "StreamArgs",
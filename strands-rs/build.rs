@@ -1,5 +1,8 @@
 //! AOT-compiles the WASM component at `cargo build` time so the runtime
 //! can deserialize native code instead of JIT-compiling ~20MB of SpiderMonkey.
+//!
+//! With the `fuel` feature a second, fuel-metered variant is built too, since
+//! agents only pay for metering when they set a fuel budget.
 
 use anyhow::Result;
 use std::path::PathBuf;
@@ -11,19 +14,38 @@ fn main() -> Result<()> {
 
     println!("cargo::rerun-if-changed={}", wasm_path.display());
 
-    // Engine config must match what lib.rs uses at runtime.
-    let mut config = Config::new();
-    config.async_support(true);
-    config.wasm_component_model(true);
-    config.target(&std::env::var("TARGET")?)?;
-
-    let engine = Engine::new(&config)?;
     let wasm_bytes = std::fs::read(&wasm_path)?;
-    let precompiled = engine.precompile_component(&wasm_bytes)?;
-
+    let target = std::env::var("TARGET")?;
     let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
-    let out_path = out_dir.join("strands-agent.cwasm");
-    std::fs::write(&out_path, &precompiled)?;
 
-    Ok(())
+    let mut variants = vec![(false, "strands-agent.cwasm")];
+    if std::env::var_os("CARGO_FEATURE_FUEL").is_some() {
+        variants.push((true, "strands-agent-metered.cwasm"));
+    }
+    std::thread::scope(|scope| {
+        let builds = variants.into_iter().map(|(metered, name)| {
+            let (wasm_bytes, target, out_path) = (&wasm_bytes, &target, out_dir.join(name));
+            scope.spawn(move || -> Result<()> {
+                let engine = Engine::new(&config(target, metered)?)?;
+                let precompiled = engine.precompile_component(wasm_bytes)?;
+                std::fs::write(&out_path, &precompiled)?;
+                Ok(())
+            })
+        });
+        builds
+            .collect::<Vec<_>>()
+            .into_iter()
+            .try_for_each(|build| build.join().expect("precompile thread panicked"))
+    })
+}
+
+/// Engine config; must match what lib.rs uses at runtime.
+fn config(target: &str, metered: bool) -> Result<Config> {
+    let mut config = Config::new();
+    config.async_support(true);
+    config.wasm_component_model(true);
+    config.epoch_interruption(true);
+    config.consume_fuel(metered);
+    config.target(target)?;
+    Ok(config)
 }
@@ -4,7 +4,7 @@
 //!   cargo run --example calculator
 
 use anyhow::Result;
-use strands::{Agent, StreamEvent};
+use strands::{Agent, StreamEvent, ToolResult};
 use tokio_stream::StreamExt;
 
 #[tokio::main]
@@ -25,7 +25,7 @@ async fn main() -> Result<()> {
                 "required": ["expression"]
             }),
             |_input: &str| -> Result<String, String> {
-                Ok(r#"{"status":"success","content":[{"text":"714"}]}"#.to_string())
+                Ok(ToolResult::success().text("714").into())
             },
         )
         .build()
@@ -39,7 +39,9 @@ async fn main() -> Result<()> {
             StreamEvent::TextDelta(text) => print!("{text}"),
             StreamEvent::Stop(data) => println!("\n[{:?}]", data.reason),
             StreamEvent::ToolUse(event) => println!("[tool-use: {}]", event.name),
-            StreamEvent::ToolResult(event) => println!("[tool-result: {} => {}]", event.status, event.content),
+            StreamEvent::ToolResult(event) => {
+                println!("[tool-result: {} => {}]", event.status, event.content);
+            }
             _ => {}
         }
     }
@@ -0,0 +1,149 @@
+//! What a tool handler knows about the call it is serving.
+//!
+//! Each invocation gets an id and a cancellation token when its stream
+//! starts. The token fires when the stream is cancelled or dropped before it
+//! finishes, or when the invocation is interrupted by its deadline or a
+//! resource limit, so long-running tools can stop early. The guest sends the
+//! agent's state with each call, so tools can read what the agent stored.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use tokio_util::sync::CancellationToken;
+
+use crate::Scratch;
+
+/// The call a tool handler is serving, passed to handlers registered with
+/// [`AgentBuilder::tool_with_context`](crate::AgentBuilder::tool_with_context)
+/// and [`AgentBuilder::async_tool_with_context`](crate::AgentBuilder::async_tool_with_context).
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use std::time::Duration;
+/// use strands::{Agent, ToolContext};
+///
+/// async fn crawl(input: String, ctx: ToolContext) -> Result<String, String> {
+///     tracing::info!(tool_use_id = %ctx.tool_use_id, invocation_id = %ctx.invocation_id, "crawling");
+///     tokio::select! {
+///         _ = ctx.cancellation.cancelled() => Err("cancelled".into()),
+///         _ = tokio::time::sleep(Duration::from_secs(30)) => Ok(input),
+///     }
+/// }
+///
+/// let agent = Agent::builder()
+///     .async_tool_with_context(
+///         "crawl",
+///         "Crawl a site.",
+///         serde_json::json!({"type": "object"}),
+///         crawl,
+///     )
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ToolContext {
+    /// The id of the tool use being served, as the model sent it.
+    pub tool_use_id: String,
+    /// Identifies the invocation the call belongs to. Unique within the
+    /// process.
+    pub invocation_id: String,
+    /// The W3C traceparent set with
+    /// [`AgentBuilder::trace_context`](crate::AgentBuilder::trace_context).
+    pub trace_context: Option<String>,
+    /// Fires when the invocation is cancelled or interrupted.
+    pub cancellation: CancellationToken,
+    agent_state: Map<String, Value>,
+    scratch: Scratch,
+}
+
+impl ToolContext {
+    /// The agent's state value under `key` when the call was made, if it is
+    /// set and deserializes as `T`.
+    pub fn state<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.state_value(key)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// The agent's raw state value under `key` when the call was made.
+    pub fn state_value(&self, key: &str) -> Option<&Value> {
+        self.agent_state.get(key)
+    }
+
+    /// The agent's whole state when the call was made. Changes the tool
+    /// makes to the copy are not seen by the agent.
+    pub fn agent_state(&self) -> &Map<String, Value> {
+        &self.agent_state
+    }
+
+    /// The agent's scratch value under `key`, if it is set and deserializes
+    /// as `T`. See [`Agent::scratch`](crate::Agent::scratch).
+    pub fn scratch<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.scratch.get(key)
+    }
+
+    /// The agent's raw scratch value under `key`.
+    pub fn scratch_value(&self, key: &str) -> Option<Value> {
+        self.scratch.get_value(key)
+    }
+
+    /// Whether the invocation has been cancelled or interrupted.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+/// The id and cancellation token of an agent's current invocation.
+#[derive(Debug, Default)]
+pub(crate) struct Invocation {
+    id: String,
+    pub(crate) cancellation: CancellationToken,
+}
+
+impl Invocation {
+    /// A new invocation with a fresh id and token.
+    pub(crate) fn start() -> Self {
+        static STARTED: AtomicU64 = AtomicU64::new(0);
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let seq = STARTED.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id: format!("{millis:x}-{seq:x}"),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// The context for a call to a tool during this invocation.
+    pub(crate) fn tool_context(
+        &self,
+        tool_use_id: &str,
+        trace_context: Option<&str>,
+        agent_state: Option<&str>,
+        scratch: &Scratch,
+    ) -> ToolContext {
+        let agent_state = agent_state
+            .and_then(|json| {
+                serde_json::from_str(json)
+                    .inspect_err(|e| {
+                        tracing::warn!(target: "strands::tools", "unreadable agent state: {e}");
+                    })
+                    .ok()
+            })
+            .unwrap_or_default();
+        ToolContext {
+            tool_use_id: tool_use_id.to_string(),
+            invocation_id: self.id.clone(),
+            trace_context: trace_context.map(str::to_string),
+            cancellation: self.cancellation.clone(),
+            agent_state,
+            scratch: scratch.clone(),
+        }
+    }
+}
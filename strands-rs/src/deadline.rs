@@ -0,0 +1,89 @@
+//! Invocation deadlines.
+//!
+//! Engines are built with epoch interruption and a background thread bumps
+//! the epoch every [`EPOCH_TICK`]. While a stream read is in flight, the
+//! store's epoch callback traps the guest once the invocation deadline has
+//! passed, which catches guest code that never yields. A guest blocked on
+//! host I/O (a hung model stream) runs no wasm, so the read itself is also
+//! bounded with a tokio timeout.
+//!
+//! Either way the instance is left unusable. The agent marks the store as
+//! poisoned and rebuilds the instance from a history checkpoint before the
+//! next call. Limit traps from [`crate::limits`] are handled the same way.
+
+use std::time::{Duration, Instant};
+
+use wasmtime::component::ResourceAny;
+use wasmtime::{Engine, Store, StoreContextMut, UpdateDeadline};
+
+use crate::{HostState, StreamEvent, bindings, limits};
+
+/// How often the epoch advances; the granularity of deadline checks.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The error raised when an invocation runs past its deadline.
+#[derive(Debug, thiserror::Error)]
+#[error("invocation deadline exceeded")]
+pub(crate) struct DeadlineExceeded;
+
+/// Advance `engine`'s epoch in the background until the engine is dropped.
+pub(crate) fn start_epoch_ticker(engine: &Engine) {
+    let engine = engine.weak();
+    std::thread::Builder::new()
+        .name("strands-epoch".into())
+        .spawn(move || {
+            while let Some(engine) = engine.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        })
+        .expect("failed to spawn epoch ticker thread");
+}
+
+/// Install the deadline check on a new store.
+pub(crate) fn configure_store(store: &mut Store<HostState>) {
+    store.epoch_deadline_callback(check_deadline);
+    store.set_epoch_deadline(1);
+}
+
+fn check_deadline(mut ctx: StoreContextMut<'_, HostState>) -> wasmtime::Result<UpdateDeadline> {
+    let state = ctx.data_mut();
+    match state.active_deadline {
+        Some(deadline) if Instant::now() >= deadline => {
+            state.poisoned = true;
+            Err(DeadlineExceeded.into())
+        }
+        _ => Ok(UpdateDeadline::Yield(1)),
+    }
+}
+
+/// Read the next batch of events, bounded by the invocation deadline.
+pub(crate) async fn read_next(
+    store: &mut Store<HostState>,
+    instance: &bindings::Agent,
+    handle: ResourceAny,
+) -> wasmtime::Result<Option<Vec<StreamEvent>>> {
+    let deadline = store.data().invocation_deadline;
+    store.data_mut().active_deadline = deadline;
+
+    let api = instance.strands_agent_api();
+    let stream = api.response_stream();
+    let read = stream.call_read_next(&mut *store, handle);
+    let result = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), read)
+            .await
+            .unwrap_or_else(|_| Err(DeadlineExceeded.into())),
+        None => read.await,
+    };
+
+    let state = store.data_mut();
+    state.active_deadline = None;
+    if result.as_ref().is_err_and(|e| {
+        e.is::<DeadlineExceeded>() || limits::exceeded(e).or(state.limiter.breach()).is_some()
+    }) {
+        state.poisoned = true;
+        state.invocation.cancellation.cancel();
+    }
+    result
+}
@@ -0,0 +1,295 @@
+//! Error type for the public [`Agent`](crate::Agent) API.
+//!
+//! Guest failures reach the host as plain strings (`stream-event::error`), so
+//! [`Error::from_guest`] classifies them by message. The guest prefixes the
+//! exception name and appends the HTTP status when the SDK reported them
+//! apart from the message. Wasmtime failures are split
+//! into traps and everything else.
+
+/// Errors returned by [`Agent`](crate::Agent) and [`AgentBuilder`](crate::AgentBuilder).
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The guest trapped (unreachable, stack overflow, out-of-bounds access, ...).
+    /// The agent instance should not be reused after a trap.
+    #[error("wasm trap: {message}")]
+    Trap {
+        trap: wasmtime::Trap,
+        message: String,
+    },
+
+    /// The guest threw an exception that is not a model or tool failure.
+    #[error("guest exception: {0}")]
+    Guest(String),
+
+    /// The model provider rejected or failed the request.
+    #[error("model error{}: {message}", status.map(|s| format!(" (HTTP {s})")).unwrap_or_default())]
+    Model {
+        message: String,
+        /// HTTP status code, when the provider reported one.
+        status: Option<u16>,
+        /// Whether retrying the same request may succeed (throttling, 5xx).
+        retryable: bool,
+    },
+
+    /// A tool was called or referenced that the agent does not know about.
+    #[error("unknown tool: {0}")]
+    UnknownTool(String),
+
+    /// The invocation was cancelled before it finished.
+    #[error("cancelled")]
+    Cancelled,
+
+    /// No credentials could be found for the configured model provider.
+    #[error("missing credentials: {0}")]
+    MissingCredentials(String),
+
+    /// The model did not produce output matching the requested type.
+    #[error("structured output failed: {0}")]
+    StructuredOutput(String),
+
+    /// The WASM component could not be loaded or instantiated.
+    #[error("failed to load component: {0}")]
+    ComponentLoad(String),
+
+    /// The invocation ran past its deadline and was interrupted.
+    #[error("invocation timed out")]
+    Timeout,
+
+    /// The agent exceeded one of its [`StoreLimits`](crate::StoreLimits).
+    #[error("{0} limit exceeded")]
+    LimitExceeded(crate::Limit),
+
+    /// A multi-agent graph or swarm was configured inconsistently.
+    #[error("invalid multi-agent configuration: {0}")]
+    MultiAgent(String),
+
+    /// Any other host-side failure.
+    #[error(transparent)]
+    Runtime(anyhow::Error),
+}
+
+/// Markers of a provider throttling the request.
+const THROTTLED: &[&str] = &["throttl", "rate limit", "too many requests", "overloaded"];
+
+/// Markers of a provider rejecting or failing the request: Bedrock exception
+/// names and Anthropic/OpenAI error types.
+const MODEL_FAILURES: &[&str] = &[
+    "validationexception",
+    "modelerrorexception",
+    "modeltimeoutexception",
+    "modelnotreadyexception",
+    "serviceunavailableexception",
+    "internalserverexception",
+    "accessdeniedexception",
+    "invalid_request_error",
+    "authentication_error",
+    "api_error",
+    "context window",
+    "context_length_exceeded",
+];
+
+/// A `Result` alias defaulting to [`Error`].
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Classify an error message reported by the guest.
+    pub(crate) fn from_guest(message: String) -> Self {
+        let lower = message.to_lowercase();
+
+        if let Some(name) = message
+            .strip_prefix("Tool '")
+            .and_then(|rest| rest.strip_suffix("' not found in registry"))
+        {
+            return Self::UnknownTool(name.to_string());
+        }
+
+        if lower.contains("credential")
+            && ["missing", "could not", "unable to", "not found", "no valid"]
+                .iter()
+                .any(|p| lower.contains(p))
+        {
+            return Self::MissingCredentials(message);
+        }
+
+        if [
+            "aborterror",
+            "operation was aborted",
+            "request was aborted",
+            "was cancelled",
+        ]
+        .iter()
+        .any(|p| lower.contains(p))
+            || lower == "cancelled"
+        {
+            return Self::Cancelled;
+        }
+
+        let status = parse_status(&message);
+        let throttled = THROTTLED.iter().any(|p| lower.contains(p));
+        let model =
+            throttled || status.is_some() || MODEL_FAILURES.iter().any(|p| lower.contains(p));
+        if model {
+            let retryable = throttled || status.is_some_and(|s| s == 429 || s >= 500);
+            return Self::Model {
+                message,
+                status: status.or(throttled.then_some(429)),
+                retryable,
+            };
+        }
+
+        Self::Guest(message)
+    }
+
+    /// Whether retrying the same request may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Model {
+                retryable: true,
+                ..
+            }
+        )
+    }
+}
+
+impl From<wasmtime::Error> for Error {
+    fn from(e: wasmtime::Error) -> Self {
+        if e.is::<crate::deadline::DeadlineExceeded>() {
+            return Self::Timeout;
+        }
+        if let Some(limit) = crate::limits::exceeded(&e) {
+            return Self::LimitExceeded(limit);
+        }
+        match e.downcast_ref::<wasmtime::Trap>() {
+            Some(trap) => Self::Trap {
+                trap: *trap,
+                message: format!("{e:#}"),
+            },
+            None => Self::Runtime(e),
+        }
+    }
+}
+
+/// Find an HTTP status code in a provider error message. SDKs format these as
+/// `429 {...}` (Anthropic, OpenAI), `status code 503` or `httpStatusCode: 500` (AWS).
+fn parse_status(message: &str) -> Option<u16> {
+    let code = |s: &str| {
+        let digits: String = s.chars().take_while(char::is_ascii_digit).collect();
+        digits
+            .parse::<u16>()
+            .ok()
+            .filter(|c| digits.len() == 3 && (400..600).contains(c))
+    };
+
+    // A leading code only counts when a status line or JSON body follows it.
+    if let Some(rest) = message.get(3..)
+        && (rest.starts_with(" {")
+            || rest.starts_with(' ') && rest[1..].starts_with(char::is_uppercase))
+        && let Some(c) = code(message)
+    {
+        return Some(c);
+    }
+
+    let lower = message.to_lowercase();
+    [
+        "status code ",
+        "status: ",
+        "statuscode: ",
+        "statuscode\":",
+        "http ",
+    ]
+    .iter()
+    .find_map(|marker| {
+        let idx = lower.find(marker)?;
+        code(lower[idx + marker.len()..].trim_start())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The variant a message is classified as, with its status and
+    /// retryability for model errors.
+    fn classify(message: &str) -> String {
+        match Error::from_guest(message.to_string()) {
+            Error::Model {
+                status, retryable, ..
+            } => format!("model {status:?} retryable={retryable}"),
+            Error::UnknownTool(name) => format!("unknown tool {name}"),
+            Error::MissingCredentials(_) => "credentials".into(),
+            Error::Cancelled => "cancelled".into(),
+            Error::Guest(_) => "guest".into(),
+            other => format!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn classifies_guest_messages() {
+        let cases = [
+            (
+                r#"429 {"type":"error","error":{"type":"rate_limit_error"}}"#,
+                "model Some(429) retryable=true",
+            ),
+            (
+                "400 Invalid parameter: max_tokens",
+                "model Some(400) retryable=false",
+            ),
+            (
+                r#"529 {"type":"error","error":{"type":"overloaded_error"}}"#,
+                "model Some(529) retryable=true",
+            ),
+            (
+                "ThrottlingException: Too many requests, please wait",
+                "model Some(429) retryable=true",
+            ),
+            (
+                "ValidationException: The provided model identifier is invalid.",
+                "model None retryable=false",
+            ),
+            (
+                "ServiceUnavailableException: status code 503",
+                "model Some(503) retryable=true",
+            ),
+            (
+                "Input is too long for the model's context window",
+                "model None retryable=false",
+            ),
+            ("Tool 'lookup' not found in registry", "unknown tool lookup"),
+            (
+                "Could not load credentials from any providers",
+                "credentials",
+            ),
+            ("AbortError: The operation was aborted", "cancelled"),
+            ("cancelled", "cancelled"),
+            ("TypeError: x is undefined", "guest"),
+            ("Tool 'model_lookup' failed", "guest"),
+            ("Unhandled exception in callback", "guest"),
+            ("500 items were processed before the crash", "guest"),
+            ("404", "guest"),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(classify(message), expected, "{message}");
+        }
+    }
+
+    #[test]
+    fn parses_status_codes() {
+        let cases = [
+            ("429 {}", Some(429)),
+            ("503 Service Unavailable", Some(503)),
+            ("request failed with status code 502", Some(502)),
+            ("Error { httpStatusCode: 500 }", Some(500)),
+            ("HTTP 404 while fetching", Some(404)),
+            ("status: 200", None),
+            ("200 OK", None),
+            ("42 {}", None),
+            ("4290 {}", None),
+            ("no status here", None),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(parse_status(message), expected, "{message}");
+        }
+    }
+}
@@ -0,0 +1,420 @@
+//! Lifecycle hooks.
+//!
+//! A [`HookRegistry`] holds typed callbacks that run synchronously at the
+//! agent's lifecycle points. The guest calls the `host-hooks` import at each
+//! point and waits for the callbacks to return, so a callback sees the agent
+//! exactly where it stopped. [`BeforeToolCall`] callbacks can cancel the call
+//! or rewrite its input, [`AfterToolCall`] callbacks can rewrite the result,
+//! and [`MessageAdded`] carries each message appended to the conversation.
+//!
+//! Callbacks for one event run in the order they were added, each seeing the
+//! changes made by the ones before it.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::bindings::strands::agent::host_hooks as wit;
+use crate::{LifecycleEventType, Message, ToolResult, ToolUse};
+
+type Callback = Arc<dyn Fn(&mut dyn Any) + Send + Sync>;
+
+/// An event a [`HookRegistry`] callback can be added for.
+pub trait HookEvent: Any + sealed::Sealed {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! hook_events {
+    ($($(#[$doc:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            #[non_exhaustive]
+            pub struct $name;
+
+            impl sealed::Sealed for $name {}
+            impl HookEvent for $name {}
+        )*
+    };
+}
+
+hook_events! {
+    /// The agent finished constructing.
+    Initialized,
+    /// An invocation is starting.
+    BeforeInvocation,
+    /// An invocation finished.
+    AfterInvocation,
+    /// The model is about to be called.
+    BeforeModelCall,
+    /// A model call finished.
+    AfterModelCall,
+}
+
+/// A message was appended to the conversation.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MessageAdded {
+    pub message: Message,
+}
+
+/// A tool is about to run. Edit `tool_use.input` to change what the tool
+/// receives, or [`cancel`](Self::cancel) the call.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BeforeToolCall {
+    pub tool_use: ToolUse,
+    cancel: Option<String>,
+}
+
+impl BeforeToolCall {
+    /// Skip the tool. The model receives an error result with `reason`.
+    pub fn cancel(&mut self, reason: impl Into<String>) {
+        self.cancel = Some(reason.into());
+    }
+
+    /// The reason the call was cancelled, if it was.
+    pub fn cancelled(&self) -> Option<&str> {
+        self.cancel.as_deref()
+    }
+}
+
+/// A tool finished. Edit `result` to change what the model receives.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct AfterToolCall {
+    /// The call as the model requested it, before any input rewrite.
+    pub tool_use: ToolUse,
+    pub result: ToolResult,
+}
+
+impl sealed::Sealed for MessageAdded {}
+impl HookEvent for MessageAdded {}
+impl sealed::Sealed for BeforeToolCall {}
+impl HookEvent for BeforeToolCall {}
+impl sealed::Sealed for AfterToolCall {}
+impl HookEvent for AfterToolCall {}
+
+/// Callbacks for lifecycle events, added to an agent with
+/// [`AgentBuilder::hooks`](crate::AgentBuilder::hooks).
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::Agent;
+/// use strands::hooks::{BeforeToolCall, HookRegistry, MessageAdded};
+///
+/// let mut hooks = HookRegistry::new();
+/// hooks
+///     .add_callback(|event: &mut BeforeToolCall| {
+///         if event.tool_use.name == "delete_file" {
+///             event.cancel("deleting files is not allowed");
+///         }
+///     })
+///     .add_callback(|event: &mut MessageAdded| {
+///         println!("{:?}: {}", event.message.role, event.message.text());
+///     });
+///
+/// let mut agent = Agent::builder().hooks(hooks).build().await?;
+/// agent.invoke("Clean up the temp directory.").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct HookRegistry {
+    callbacks: HashMap<TypeId, Vec<Callback>>,
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `callback` whenever an `E` event fires.
+    pub fn add_callback<E: HookEvent>(
+        &mut self,
+        callback: impl Fn(&mut E) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let callback: Callback = Arc::new(move |event: &mut dyn Any| {
+            if let Some(event) = event.downcast_mut::<E>() {
+                callback(event);
+            }
+        });
+        self.callbacks
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push(callback);
+        self
+    }
+
+    /// Whether any callback is registered for `E`.
+    pub fn has_callbacks<E: HookEvent>(&self) -> bool {
+        self.callbacks.contains_key(&TypeId::of::<E>())
+    }
+
+    fn fire<E: HookEvent>(&self, event: &mut E) {
+        for callback in self.callbacks.get(&TypeId::of::<E>()).into_iter().flatten() {
+            callback(event);
+        }
+    }
+
+    /// Run the callbacks for an event raised by the guest and tell it how to
+    /// proceed.
+    pub(crate) fn run(&self, event: wit::HookEvent) -> wit::HookAction {
+        match event.event_type {
+            LifecycleEventType::Initialized => self.fire(&mut Initialized),
+            LifecycleEventType::BeforeInvocation => self.fire(&mut BeforeInvocation),
+            LifecycleEventType::AfterInvocation => self.fire(&mut AfterInvocation),
+            LifecycleEventType::BeforeModelCall => self.fire(&mut BeforeModelCall),
+            LifecycleEventType::AfterModelCall => self.fire(&mut AfterModelCall),
+            LifecycleEventType::MessageAdded => {
+                if self.has_callbacks::<MessageAdded>()
+                    && let Some(message) = parse(event.message.as_deref(), "message")
+                {
+                    self.fire(&mut MessageAdded { message });
+                }
+            }
+            LifecycleEventType::BeforeToolCall => {
+                if self.has_callbacks::<BeforeToolCall>()
+                    && let Some(tool_use) = parse(event.tool_use.as_deref(), "tool use")
+                {
+                    return self.before_tool_call(&tool_use);
+                }
+            }
+            LifecycleEventType::AfterToolCall => {
+                if self.has_callbacks::<AfterToolCall>()
+                    && let Some(tool_use) = parse(event.tool_use.as_deref(), "tool use")
+                    && let Some(result) = parse(event.tool_result.as_deref(), "tool result")
+                {
+                    return self.after_tool_call(tool_use, result);
+                }
+            }
+        }
+        wit::HookAction::Proceed
+    }
+
+    /// Run the before-tool-call callbacks for a call and report how they
+    /// changed it.
+    pub(crate) fn before_tool_call(&self, tool_use: &ToolUse) -> wit::HookAction {
+        if !self.has_callbacks::<BeforeToolCall>() {
+            return wit::HookAction::Proceed;
+        }
+        let mut event = BeforeToolCall {
+            tool_use: tool_use.clone(),
+            cancel: None,
+        };
+        self.fire(&mut event);
+        if let Some(reason) = event.cancel {
+            wit::HookAction::Cancel(reason)
+        } else if event.tool_use.input != tool_use.input {
+            wit::HookAction::Replace(event.tool_use.input.to_string())
+        } else {
+            wit::HookAction::Proceed
+        }
+    }
+
+    fn after_tool_call(&self, tool_use: ToolUse, result: ToolResult) -> wit::HookAction {
+        let mut event = AfterToolCall {
+            tool_use,
+            result: result.clone(),
+        };
+        self.fire(&mut event);
+        if event.result == result {
+            return wit::HookAction::Proceed;
+        }
+        match serde_json::to_string(&event.result) {
+            Ok(json) => wit::HookAction::Replace(json),
+            Err(_) => wit::HookAction::Proceed,
+        }
+    }
+}
+
+impl fmt::Debug for HookRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookRegistry")
+            .field(
+                "callbacks",
+                &self.callbacks.values().map(Vec::len).sum::<usize>(),
+            )
+            .finish()
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(json: Option<&str>, what: &str) -> Option<T> {
+    let json = json?;
+    match serde_json::from_str(json) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!(target: "strands::hooks", "skipping hooks, bad {what}: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    fn event(event_type: LifecycleEventType) -> wit::HookEvent {
+        wit::HookEvent {
+            event_type,
+            tool_use: None,
+            tool_result: None,
+            message: None,
+            interrupt_response: None,
+        }
+    }
+
+    fn tool_event(event_type: LifecycleEventType) -> wit::HookEvent {
+        wit::HookEvent {
+            tool_use: Some(r#"{"name": "add", "toolUseId": "call-0", "input": {"n": 1}}"#.into()),
+            tool_result: Some(
+                r#"{"toolUseId": "call-0", "status": "success", "content": [{"text": "2"}]}"#
+                    .into(),
+            ),
+            ..event(event_type)
+        }
+    }
+
+    fn tool_use(input: Value) -> ToolUse {
+        ToolUse {
+            name: "add".into(),
+            tool_use_id: "call-0".into(),
+            input,
+            reasoning_signature: None,
+        }
+    }
+
+    #[test]
+    fn run_fires_the_callbacks_for_each_event() {
+        let log = Log::default();
+        let mut hooks = HookRegistry::new();
+        let entry = |name: &'static str| {
+            let log = Arc::clone(&log);
+            move || log.lock().unwrap().push(name.to_string())
+        };
+        let (initialized, before, after) = (entry("init"), entry("before"), entry("after"));
+        hooks
+            .add_callback(move |_: &mut Initialized| initialized())
+            .add_callback(move |_: &mut BeforeInvocation| before())
+            .add_callback(move |_: &mut AfterInvocation| after());
+        let message_log = Arc::clone(&log);
+        hooks.add_callback(move |event: &mut MessageAdded| {
+            message_log.lock().unwrap().push(event.message.text());
+        });
+
+        for event_type in [
+            LifecycleEventType::Initialized,
+            LifecycleEventType::BeforeInvocation,
+            LifecycleEventType::BeforeModelCall,
+            LifecycleEventType::AfterInvocation,
+        ] {
+            assert!(matches!(
+                hooks.run(event(event_type)),
+                wit::HookAction::Proceed
+            ));
+        }
+        let message = wit::HookEvent {
+            message: Some(r#"{"role": "user", "content": [{"text": "hi"}]}"#.into()),
+            ..event(LifecycleEventType::MessageAdded)
+        };
+        hooks.run(message);
+        // An unreadable message skips the callbacks.
+        let garbled = wit::HookEvent {
+            message: Some("not json".into()),
+            ..event(LifecycleEventType::MessageAdded)
+        };
+        hooks.run(garbled);
+
+        assert_eq!(*log.lock().unwrap(), ["init", "before", "after", "hi"]);
+    }
+
+    #[test]
+    fn callbacks_run_in_order_and_see_earlier_changes() {
+        let log = Log::default();
+        let mut hooks = HookRegistry::new();
+        for step in 1..=3 {
+            let log = Arc::clone(&log);
+            hooks.add_callback(move |event: &mut BeforeToolCall| {
+                let n = event.tool_use.input["n"].as_i64().unwrap();
+                log.lock().unwrap().push(format!("{step}: n={n}"));
+                event.tool_use.input["n"] = json!(n * 10);
+            });
+        }
+
+        let action = hooks.run(tool_event(LifecycleEventType::BeforeToolCall));
+        assert!(matches!(action, wit::HookAction::Replace(input) if input == r#"{"n":1000}"#));
+        assert_eq!(*log.lock().unwrap(), ["1: n=1", "2: n=10", "3: n=100"]);
+    }
+
+    #[test]
+    fn before_tool_call_combines_cancel_replace_and_proceed() {
+        let original = tool_use(json!({"n": 1}));
+
+        let mut unchanged = HookRegistry::new();
+        unchanged.add_callback(|_: &mut BeforeToolCall| {});
+        assert!(matches!(
+            unchanged.before_tool_call(&original),
+            wit::HookAction::Proceed
+        ));
+
+        // Rewriting the input back to what it was is no change.
+        let mut restored = HookRegistry::new();
+        restored
+            .add_callback(|event: &mut BeforeToolCall| event.tool_use.input = json!({"n": 2}))
+            .add_callback(|event: &mut BeforeToolCall| event.tool_use.input = json!({"n": 1}));
+        assert!(matches!(
+            restored.before_tool_call(&original),
+            wit::HookAction::Proceed
+        ));
+
+        // A cancel wins over any rewrite, and later callbacks see it.
+        let seen = Log::default();
+        let seen_by_last = Arc::clone(&seen);
+        let mut cancelled = HookRegistry::new();
+        cancelled
+            .add_callback(|event: &mut BeforeToolCall| event.cancel("not today"))
+            .add_callback(move |event: &mut BeforeToolCall| {
+                let reason = event.cancelled().unwrap_or_default().to_string();
+                seen_by_last.lock().unwrap().push(reason);
+                event.tool_use.input = json!({"n": 2});
+            });
+        assert!(matches!(
+            cancelled.before_tool_call(&original),
+            wit::HookAction::Cancel(reason) if reason == "not today"
+        ));
+        assert_eq!(*seen.lock().unwrap(), ["not today"]);
+    }
+
+    #[test]
+    fn after_tool_call_replaces_only_a_changed_result() {
+        let mut unchanged = HookRegistry::new();
+        unchanged
+            .add_callback(|event: &mut AfterToolCall| assert_eq!(event.tool_use.input["n"], 1));
+        assert!(matches!(
+            unchanged.run(tool_event(LifecycleEventType::AfterToolCall)),
+            wit::HookAction::Proceed
+        ));
+
+        let mut redacted = HookRegistry::new();
+        redacted.add_callback(|event: &mut AfterToolCall| {
+            event.result = ToolResult::error("redacted");
+        });
+        let wit::HookAction::Replace(json) =
+            redacted.run(tool_event(LifecycleEventType::AfterToolCall))
+        else {
+            panic!("expected the result to be replaced");
+        };
+        let result: ToolResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(result, ToolResult::error("redacted"));
+    }
+}
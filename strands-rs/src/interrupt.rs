@@ -0,0 +1,165 @@
+//! Human-in-the-loop interrupts.
+//!
+//! Tools registered with [`AgentBuilder::interrupt_before`](crate::AgentBuilder::interrupt_before),
+//! and calls a [`ToolPolicy`](crate::ToolPolicy) asks about, need approval
+//! before they run. The host's before-tool-call hook asks the guest to pause
+//! the call; the guest raises an [`Interrupt`] on the stream under an ID it
+//! issues and waits for the answer, which the host sends back through
+//! `response-stream.respond`. The guest then runs the hook again with the
+//! answer, and the host approves or rejects the call.
+//!
+//! The builder's interrupt handler can answer interrupts as they arrive.
+//! Otherwise [`Agent::invoke`](crate::Agent::invoke) returns them in
+//! [`AgentResult::interrupts`](crate::AgentResult::interrupts) and keeps the
+//! paused stream, and [`Agent::resume`](crate::Agent::resume) answers them and
+//! reads the same stream on. A [`ResponseStream`](crate::ResponseStream)
+//! yields them as [`StreamEvent::Interrupt`](crate::StreamEvent::Interrupt)
+//! and pauses until they are answered with
+//! [`ResponseStream::respond`](crate::ResponseStream::respond).
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Value;
+use wasmtime::component::ResourceAny;
+
+use crate::bindings::strands::agent::types::HookAction;
+use crate::{Permission, ToolUse, result};
+
+type InterruptFuture = Pin<Box<dyn Future<Output = Option<Value>> + Send>>;
+pub(crate) type InterruptHandlerFn = Arc<dyn Fn(Interrupt) -> InterruptFuture + Send + Sync>;
+
+/// A request for input from outside the agent loop.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Interrupt {
+    /// Identifier issued by the guest, used to match an [`InterruptResponse`].
+    pub id: String,
+    /// What kind of input is needed, e.g. `tool_approval`.
+    pub name: String,
+    /// Details for whoever answers, e.g. the tool and its input.
+    pub reason: Value,
+}
+
+impl Interrupt {
+    /// The interrupt a [`StreamEvent::Interrupt`](crate::StreamEvent::Interrupt)
+    /// carries.
+    pub fn from_event(payload: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(payload)
+    }
+
+    /// Build the response to this interrupt.
+    pub fn respond(&self, response: impl Into<Value>) -> InterruptResponse {
+        InterruptResponse {
+            interrupt_id: self.id.clone(),
+            response: response.into(),
+        }
+    }
+}
+
+/// An answer to an [`Interrupt`], passed to [`Agent::resume`](crate::Agent::resume)
+/// or [`ResponseStream::respond`](crate::ResponseStream::respond).
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptResponse {
+    pub interrupt_id: String,
+    pub response: Value,
+}
+
+impl InterruptResponse {
+    /// The `response-stream.respond` payload.
+    pub(crate) fn to_payload(&self) -> String {
+        serde_json::json!({
+            "interruptId": self.interrupt_id,
+            "response": self.response,
+        })
+        .to_string()
+    }
+}
+
+/// An invocation stopped on unanswered interrupts. Its stream stays open in
+/// the guest until [`Agent::resume`](crate::Agent::resume) answers them.
+pub(crate) struct Suspended {
+    pub(crate) handle: ResourceAny,
+    /// The invocation's events so far, including its unanswered interrupts.
+    pub(crate) collector: result::Collector,
+}
+
+/// Interrupt configuration and approval state for one agent.
+#[derive(Default)]
+pub(crate) struct Interrupts {
+    pub(crate) handler: Option<InterruptHandlerFn>,
+    pub(crate) tools: HashSet<String>,
+    /// Hook outcomes of calls waiting for approval, by tool use ID.
+    awaiting: HashMap<String, HookAction>,
+    /// Approved tool uses; each approval admits one dispatch.
+    approved: HashSet<String>,
+}
+
+impl Interrupts {
+    /// The same configuration with no approval state.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            tools: self.tools.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Forget calls awaiting or holding approval.
+    pub(crate) fn clear(&mut self) {
+        self.awaiting.clear();
+        self.approved.clear();
+    }
+
+    /// Whether a call to `tool` with this policy permission needs approval.
+    pub(crate) fn requires_approval(&self, tool: &str, permission: Permission) -> bool {
+        permission == Permission::Ask || self.tools.contains(tool)
+    }
+
+    /// Hold a call's hook outcome and ask the guest to raise a
+    /// `tool_approval` interrupt for it.
+    pub(crate) fn request_approval(
+        &mut self,
+        tool_use: &ToolUse,
+        input: &Value,
+        outcome: HookAction,
+    ) -> HookAction {
+        self.awaiting.insert(tool_use.tool_use_id.clone(), outcome);
+        let interrupt = serde_json::json!({
+            "name": "tool_approval",
+            "reason": {
+                "tool": tool_use.name,
+                "toolUseId": tool_use.tool_use_id,
+                "input": input,
+            },
+        });
+        HookAction::Interrupt(interrupt.to_string())
+    }
+
+    /// Apply the answer to a call's approval interrupt: `true` approves it,
+    /// anything else rejects it with the answer as the reason.
+    pub(crate) fn answer(&mut self, tool_use_id: &str, response: &str) -> HookAction {
+        let outcome = self
+            .awaiting
+            .remove(tool_use_id)
+            .unwrap_or(HookAction::Proceed);
+        match serde_json::from_str(response).unwrap_or(Value::Null) {
+            Value::Bool(true) => {
+                self.approved.insert(tool_use_id.to_string());
+                outcome
+            }
+            Value::String(reason) => {
+                HookAction::Cancel(format!("tool call was rejected: {reason}"))
+            }
+            Value::Null => HookAction::Cancel("tool call was not approved".into()),
+            response => HookAction::Cancel(format!("tool call was rejected: {response}")),
+        }
+    }
+
+    /// Consume the approval of a tool use, if it has one.
+    pub(crate) fn take_approval(&mut self, tool_use_id: &str) -> bool {
+        self.approved.remove(tool_use_id)
+    }
+}
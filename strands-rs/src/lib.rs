@@ -4,54 +4,126 @@
 //! The agent maintains conversation history across calls to [`Agent::stream`]
 //! and [`Agent::invoke`].
 
-use std::collections::HashMap;
-use std::sync::OnceLock;
-
-use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use futures_util::StreamExt;
 use wasmtime::component::{Component, HasSelf, Linker, ResourceTable};
 use wasmtime::{Config, Engine, Store};
-use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
 use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
 
+type AotCache = (Engine, Component, Linker<HostState>);
+
 /// Cached Engine + Component + Linker for the AOT-precompiled WASM component.
 /// Created once per process on first agent construction. Eliminates ~250ms of
 /// Engine/Component/Linker setup on every subsequent agent construction.
-static AOT_CACHE: OnceLock<(Engine, Component, Linker<HostState>)> = OnceLock::new();
+static AOT_CACHE: OnceLock<AotCache> = OnceLock::new();
+
+/// [`AOT_CACHE`] for agents with a fuel budget.
+static METERED_AOT_CACHE: OnceLock<AotCache> = OnceLock::new();
+
+/// The component precompiled by `build.rs`.
+const AOT_COMPONENT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/strands-agent.cwasm"));
+
+/// The component precompiled by `build.rs` with fuel metering.
+#[cfg(feature = "fuel")]
+const METERED_AOT_COMPONENT: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/strands-agent-metered.cwasm"));
+
+fn aot_cache(metered: bool) -> Result<&'static AotCache> {
+    let component = aot_component(metered)?;
+    let cache = if metered {
+        &METERED_AOT_CACHE
+    } else {
+        &AOT_CACHE
+    };
+    Ok(cache.get_or_init(|| init_aot_cache(component, metered)))
+}
+
+fn init_aot_cache(component: &[u8], metered: bool) -> AotCache {
+    let engine = Engine::new(&wasm_config(metered)).expect("failed to create wasmtime engine");
+    deadline::start_epoch_ticker(&engine);
 
-fn init_aot_cache() -> (Engine, Component, Linker<HostState>) {
+    let component = unsafe { Component::deserialize(&engine, component) }
+        .expect("failed to load precompiled strands component");
+    let linker = new_linker(&engine).expect("failed to link host interfaces");
+
+    (engine, component, linker)
+}
+
+/// The precompiled component matching [`wasm_config`]. The metered one is
+/// only built with the `fuel` feature.
+fn aot_component(metered: bool) -> Result<&'static [u8]> {
+    match metered {
+        false => Ok(AOT_COMPONENT),
+        #[cfg(feature = "fuel")]
+        true => Ok(METERED_AOT_COMPONENT),
+        #[cfg(not(feature = "fuel"))]
+        true => Err(Error::ComponentLoad(
+            "fuel_per_invocation needs the `fuel` feature to use the precompiled component".into(),
+        )),
+    }
+}
+
+/// Engine settings shared by every runtime. Must match `build.rs`, or the
+/// precompiled component will not load. Fuel metering slows the guest down,
+/// so it is only enabled for agents with a fuel budget.
+fn wasm_config(metered: bool) -> Config {
     let mut wasm_config = Config::new();
     wasm_config.async_support(true);
     wasm_config.wasm_component_model(true);
     wasm_config.max_wasm_stack(32 * 1024 * 1024);
     wasm_config.async_stack_size(64 * 1024 * 1024);
+    wasm_config.epoch_interruption(true);
+    wasm_config.consume_fuel(metered);
+    wasm_config
+}
 
-    let engine = Engine::new(&wasm_config).expect("failed to create wasmtime engine");
-
-    let component = unsafe {
-        Component::deserialize(
-            &engine,
-            include_bytes!(concat!(env!("OUT_DIR"), "/strands-agent.cwasm")),
-        )
-    }
-    .expect("failed to load precompiled strands component");
-
-    let mut linker = Linker::<HostState>::new(&engine);
-    wasmtime_wasi::p2::add_to_linker_async(&mut linker)
-        .expect("failed to add WASI to linker");
-    wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)
-        .expect("failed to add WASI HTTP to linker");
+/// A linker with WASI, WASI HTTP and the host's own imports.
+fn new_linker(engine: &Engine) -> wasmtime::Result<Linker<HostState>> {
+    let mut linker = Linker::<HostState>::new(engine);
+    wasmtime_wasi::p2::add_to_linker_async(&mut linker)?;
+    wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
     bindings::strands::agent::tool_provider::add_to_linker::<HostState, HasSelf<HostState>>(
         &mut linker,
         |state: &mut HostState| state,
-    )
-    .expect("failed to add tool-provider to linker");
+    )?;
     bindings::strands::agent::host_log::add_to_linker::<HostState, HasSelf<HostState>>(
         &mut linker,
         |state: &mut HostState| state,
-    )
-    .expect("failed to add host-log to linker");
+    )?;
+    bindings::strands::agent::host_hooks::add_to_linker::<HostState, HasSelf<HostState>>(
+        &mut linker,
+        |state: &mut HostState| state,
+    )?;
+    Ok(linker)
+}
 
-    (engine, component, linker)
+/// Where an agent's instances come from.
+#[derive(Clone)]
+enum Runtime {
+    /// The process-wide [`AOT_CACHE`].
+    Aot,
+    /// Compiled from `strands-agent.wasm` on every instantiation (dev mode).
+    Jit,
+    /// Pre-linked by an [`AgentPool`] on its pooling-allocator engine.
+    Pooled(bindings::AgentPre<HostState>),
+}
+
+impl Runtime {
+    /// The runtime for an agent that leaves the pool. Pool slots are sized
+    /// for the pool's own agents, so it moves to the process-wide cache.
+    fn unpooled(&self) -> Self {
+        match self {
+            Self::Pooled(_) => Self::Aot,
+            other => other.clone(),
+        }
+    }
 }
 
 /// Generated by `wasmtime::component::bindgen!` from `wit/agent.wit`.
@@ -63,15 +135,17 @@ mod bindings {
     wasmtime::component::bindgen!({
         path: "../wit",
         world: "agent",
+        imports: { "strands:agent/tool-provider": async },
         exports: { default: async },
         additional_derives: [strands_derive::Export],
     });
 }
 
 pub use bindings::strands::agent::types::{
-    AnthropicConfig, BedrockConfig, GeminiConfig, LifecycleEvent, LifecycleEventType,
-    MetadataEvent, Metrics, ModelConfig, ModelParams, OpenaiConfig, StopData, StopReason,
-    StreamEvent, ToolResultEvent, ToolSpec, ToolUseEvent, Usage,
+    AnthropicConfig, BedrockConfig, FileStorageConfig, GeminiConfig, LifecycleEvent,
+    LifecycleEventType, MetadataEvent, Metrics, ModelConfig, ModelParams, OpenaiConfig,
+    S3StorageConfig, SessionConfig, StopData, StopReason, StorageConfig, StreamEvent,
+    ToolResultEvent, ToolSpec, ToolUseEvent, Usage,
 };
 
 pub use bindings::strands::agent::types::{
@@ -81,28 +155,277 @@ pub use bindings::strands::agent::types::{
 
 uniffi::setup_scaffolding!();
 
+pub mod hooks;
+pub mod multiagent;
 pub mod uniffi_bridge;
 
+mod context;
+mod deadline;
+mod error;
+mod interrupt;
+mod limits;
+mod message;
+mod policy;
+mod pool;
+mod resilience;
+mod result;
+mod scratch;
+mod stream;
+mod structured_output;
+mod tool;
+mod validation;
+
+pub use context::ToolContext;
+pub use error::{Error, Result};
+pub use hooks::HookRegistry;
+pub use interrupt::{Interrupt, InterruptResponse};
+pub use limits::{Limit, ResourceUsage, StoreLimits};
+pub use message::{
+    CachePoint, ContentBlock, Document, DocumentSource, Image, ImageSource, Message, Reasoning,
+    Role, S3Location, ToolResult, ToolResultContent, ToolResultStatus, ToolUse,
+};
+
+pub use policy::{Permission, Predicate, ToolPolicy, ToolRule};
+pub use pool::{AgentPool, AgentPoolBuilder, PooledAgent};
+pub use resilience::{CircuitBreaker, ToolFailure, ToolOptions, retryable};
+pub use result::{AgentResult, ModelCall, ToolCall};
+pub use schemars::JsonSchema;
+pub use scratch::Scratch;
+pub use strands_derive::tool;
+pub use stream::ResponseStream;
+pub use tokio_util::sync::CancellationToken;
+pub use tool::{Tool, tool_spec};
+
+/// Re-exports used by code generated from [`macro@tool`]. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use schemars;
+    pub use serde;
+}
+
 use bindings::strands::agent::types::AgentConfig as WitAgentConfig;
+use bindings::strands::agent::types::HookAction;
 
 pub use wasmtime::component::ResourceAny;
 
+/// A boxed future returned by tool handlers. Owns its inputs so the host can
+/// await it across wasmtime fiber suspension points.
+type ToolFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
 /// A function that dispatches tool calls from the guest.
-/// Receives `(tool_name, input_json, tool_use_id)` and returns `Ok(result_json)` or `Err(error_message)`.
-type ToolDispatchFn = Box<dyn Fn(&str, &str, &str) -> Result<String, String> + Send + Sync>;
+/// Receives `(tool_name, input_json, context)` and resolves to `Ok(result_json)` or `Err(error_message)`.
+type ToolDispatchFn = Arc<dyn Fn(&str, &str, &ToolContext) -> ToolFuture + Send + Sync>;
 
-/// Per-tool handler that receives input JSON and returns result JSON or an error.
-type ToolHandlerFn = Box<dyn Fn(&str) -> Result<String, String> + Send + Sync>;
+/// Per-tool handler that receives input JSON and the call's context and
+/// resolves to result JSON or an error.
+type ToolHandlerFn = Arc<dyn Fn(&str, &ToolContext) -> ToolFuture + Send + Sync>;
 
 /// Receives `(level, message, optional_context_json)`.
-type LogHandlerFn = Box<dyn Fn(&str, &str, Option<&str>) + Send + Sync>;
+type LogHandlerFn = Arc<dyn Fn(&str, &str, Option<&str>) + Send + Sync>;
 
 struct HostState {
     ctx: WasiCtx,
     http: WasiHttpCtx,
     table: ResourceTable,
+    /// Per-tool handlers, consulted before `tool_dispatch`.
+    tool_handlers: Arc<HashMap<String, ToolHandlerFn>>,
     tool_dispatch: Option<ToolDispatchFn>,
+    tool_guard: Option<resilience::ToolGuard>,
+    tool_inputs: Arc<validation::InputValidators>,
+    sequential_tools: HashSet<String>,
+    max_parallel_tools: Option<usize>,
+    structured_output: Option<structured_output::OutputCapture>,
+    interrupts: interrupt::Interrupts,
+    tool_policy: ToolPolicy,
     log_handler: Option<LogHandlerFn>,
+    hooks: HookRegistry,
+    scratch: Scratch,
+    trace_context: Option<String>,
+    invocation: context::Invocation,
+    /// Deadline of the current invocation, if it has a timeout.
+    invocation_deadline: Option<Instant>,
+    /// The invocation deadline while a stream read is in flight, checked by
+    /// the epoch callback.
+    active_deadline: Option<Instant>,
+    limiter: limits::Limiter,
+    /// Set when a deadline or limit was hit; the instance must be rebuilt.
+    poisoned: bool,
+}
+
+impl HostState {
+    fn new() -> Self {
+        Self {
+            ctx: WasiCtxBuilder::new().inherit_stdio().inherit_env().build(),
+            http: WasiHttpCtx::new(),
+            table: ResourceTable::new(),
+            tool_handlers: Default::default(),
+            tool_dispatch: None,
+            tool_guard: None,
+            tool_inputs: Default::default(),
+            sequential_tools: HashSet::new(),
+            max_parallel_tools: None,
+            structured_output: None,
+            interrupts: Default::default(),
+            tool_policy: ToolPolicy::new(),
+            log_handler: None,
+            hooks: HookRegistry::new(),
+            scratch: Scratch::default(),
+            trace_context: None,
+            invocation: Default::default(),
+            invocation_deadline: None,
+            active_deadline: None,
+            limiter: Default::default(),
+            poisoned: false,
+        }
+    }
+
+    /// Move the host configuration into a fresh state for a new store.
+    fn renew(&mut self) -> Self {
+        Self {
+            tool_handlers: std::mem::take(&mut self.tool_handlers),
+            tool_dispatch: self.tool_dispatch.take(),
+            tool_guard: self.tool_guard.take(),
+            tool_inputs: std::mem::take(&mut self.tool_inputs),
+            sequential_tools: std::mem::take(&mut self.sequential_tools),
+            max_parallel_tools: self.max_parallel_tools,
+            interrupts: std::mem::take(&mut self.interrupts),
+            tool_policy: std::mem::take(&mut self.tool_policy),
+            log_handler: self.log_handler.take(),
+            hooks: std::mem::take(&mut self.hooks),
+            scratch: std::mem::take(&mut self.scratch),
+            trace_context: self.trace_context.take(),
+            limiter: limits::Limiter::new(self.limiter.limits.clone()),
+            ..Self::new()
+        }
+    }
+
+    /// Copy the host configuration into a fresh state for a forked agent.
+    fn fork(&self) -> Self {
+        Self {
+            tool_handlers: Arc::clone(&self.tool_handlers),
+            tool_dispatch: self.tool_dispatch.clone(),
+            tool_guard: self.tool_guard.clone(),
+            tool_inputs: Arc::clone(&self.tool_inputs),
+            sequential_tools: self.sequential_tools.clone(),
+            max_parallel_tools: self.max_parallel_tools,
+            interrupts: self.interrupts.fork(),
+            tool_policy: self.tool_policy.clone(),
+            log_handler: self.log_handler.clone(),
+            hooks: self.hooks.clone(),
+            scratch: self.scratch.detach(),
+            trace_context: self.trace_context.clone(),
+            limiter: limits::Limiter::new(self.limiter.limits.clone()),
+            ..Self::new()
+        }
+    }
+}
+
+impl HostState {
+    /// Apply the tool policy and check that calls needing approval were
+    /// approved by their interrupt, then validate the call's input. `Err` is
+    /// reported to the model as the tool result.
+    fn check_call(&mut self, tool: &str, input: &str, tool_use_id: &str) -> Result<(), String> {
+        let permission = self.tool_policy.check_json(tool, input);
+        if permission == Permission::Deny {
+            return Err(format!("tool '{tool}' is not permitted by policy"));
+        }
+        if self.interrupts.requires_approval(tool, permission)
+            && !self.interrupts.take_approval(tool_use_id)
+        {
+            return Err(format!("tool '{tool}' requires approval"));
+        }
+        self.tool_inputs.validate(tool, input)
+    }
+
+    /// Route calls to a tool added at runtime to `handler`, checking their
+    /// input against the tool's schema.
+    fn register_tool(&mut self, spec: &ToolSpec, handler: ToolHandlerFn) {
+        Arc::make_mut(&mut self.tool_inputs).insert(spec);
+        Arc::make_mut(&mut self.tool_handlers).insert(spec.name.clone(), handler);
+    }
+
+    /// Forget a tool removed at runtime.
+    fn unregister_tool(&mut self, name: &str) {
+        Arc::make_mut(&mut self.tool_inputs).remove(name);
+        Arc::make_mut(&mut self.tool_handlers).remove(name);
+    }
+
+    /// Run the before-tool-call hooks for a call, then hold it for approval
+    /// if the tool policy or [`AgentBuilder::interrupt_before`] requires it.
+    /// `response` answers the approval interrupt an earlier run raised.
+    fn before_tool_call(&mut self, tool_use: ToolUse, response: Option<&str>) -> HookAction {
+        if let Some(response) = response {
+            return self.interrupts.answer(&tool_use.tool_use_id, response);
+        }
+        let action = self.hooks.before_tool_call(&tool_use);
+        if matches!(action, HookAction::Cancel(_))
+            || self
+                .structured_output
+                .as_ref()
+                .is_some_and(|capture| capture.tool_name == tool_use.name)
+        {
+            return action;
+        }
+        let (input, permission) = match &action {
+            HookAction::Replace(json) => (
+                serde_json::from_str(json).unwrap_or(serde_json::Value::Null),
+                self.tool_policy.check_json(&tool_use.name, json),
+            ),
+            _ => {
+                let permission = self.tool_policy.check(&tool_use.name, &tool_use.input);
+                (tool_use.input.clone(), permission)
+            }
+        };
+        // A denied call is rejected with the policy's reason when dispatched.
+        if permission != Permission::Deny
+            && self
+                .interrupts
+                .requires_approval(&tool_use.name, permission)
+        {
+            return self.interrupts.request_approval(&tool_use, &input, action);
+        }
+        action
+    }
+
+    /// A call to the tool's handler, or to the dispatch function if it has
+    /// none, bounded by the tool's [`ToolOptions`]. The handler is not called
+    /// until the future is first polled.
+    fn dispatch(&self, tool: &str, input: &str, context: &ToolContext) -> ToolFuture {
+        let handler = match (self.tool_handlers.get(tool), &self.tool_dispatch) {
+            (Some(handler), _) => Arc::clone(handler),
+            (None, Some(dispatch)) => {
+                let dispatch = Arc::clone(dispatch);
+                let tool = tool.to_string();
+                Arc::new(move |input: &str, context: &ToolContext| -> ToolFuture {
+                    dispatch(&tool, input, context)
+                })
+            }
+            (None, None) => {
+                let err = format!("no handler for tool '{tool}'");
+                return Box::pin(async move { Err(err) });
+            }
+        };
+        let guard = self.tool_guard.clone();
+        let (tool, input, context) = (tool.to_string(), input.to_string(), context.clone());
+        Box::pin(async move {
+            let result = match &guard {
+                Some(guard) => guard.call(&tool, handler, &input, &context).await,
+                None => handler(&input, &context).await,
+            };
+            result.map_err(resilience::strip_retryable)
+        })
+    }
+
+    /// The context for a call to a tool in the current invocation, with the
+    /// agent state the guest sent along with it.
+    fn tool_context(&self, tool_use_id: &str, agent_state: Option<&str>) -> ToolContext {
+        self.invocation.tool_context(
+            tool_use_id,
+            self.trace_context.as_deref(),
+            agent_state,
+            &self.scratch,
+        )
+    }
 }
 
 impl WasiView for HostState {
@@ -147,33 +470,96 @@ impl bindings::strands::agent::host_log::Host for HostState {
     }
 }
 
+impl bindings::strands::agent::host_hooks::Host for HostState {
+    fn on_event(
+        &mut self,
+        event: bindings::strands::agent::host_hooks::HookEvent,
+    ) -> bindings::strands::agent::host_hooks::HookAction {
+        if event.event_type == LifecycleEventType::BeforeToolCall
+            && let Some(tool_use) = event
+                .tool_use
+                .as_deref()
+                .and_then(|json| serde_json::from_str(json).ok())
+        {
+            return self.before_tool_call(tool_use, event.interrupt_response.as_deref());
+        }
+        self.hooks.run(event)
+    }
+}
+
 impl bindings::strands::agent::tool_provider::Host for HostState {
-    fn call_tool(
+    async fn call_tool(
         &mut self,
         args: bindings::strands::agent::types::CallToolArgs,
     ) -> Result<String, String> {
-        match &self.tool_dispatch {
-            Some(dispatch) => dispatch(&args.name, &args.input, &args.tool_use_id),
-            None => Err(format!("no handler for tool '{}'", args.name)),
+        if let Some(capture) = &mut self.structured_output
+            && capture.tool_name == args.name
+        {
+            return capture.accept(&args.input);
         }
+        self.check_call(&args.name, &args.input, &args.tool_use_id)?;
+        let context = self.tool_context(&args.tool_use_id, args.agent_state.as_deref());
+        self.dispatch(&args.name, &args.input, &context).await
     }
 
-    fn call_tools(
+    /// Runs the batch concurrently, up to `max_parallel_tools` at a time.
+    /// A sequential-only tool runs alone: it waits for the calls in flight
+    /// and holds back the rest until it finishes. Results are returned in the
+    /// order of `args.calls`.
+    async fn call_tools(
         &mut self,
         args: bindings::strands::agent::types::CallToolsArgs,
     ) -> Vec<Result<String, String>> {
-        match &self.tool_dispatch {
-            Some(dispatch) => args
-                .calls
-                .iter()
-                .map(|call| dispatch(&call.name, &call.input, &call.tool_use_id))
-                .collect(),
-            None => args
-                .calls
-                .iter()
-                .map(|call| Err(format!("no handler for tool '{}'", call.name)))
-                .collect(),
+        let mut approvals = Vec::with_capacity(args.calls.len());
+        for call in &args.calls {
+            let structured_output = self
+                .structured_output
+                .as_ref()
+                .is_some_and(|capture| capture.tool_name == call.name);
+            approvals.push(if structured_output {
+                Ok(())
+            } else {
+                self.check_call(&call.name, &call.input, &call.tool_use_id)
+            });
         }
+
+        let exclusive = Arc::new(tokio::sync::RwLock::new(()));
+        let limit = self.max_parallel_tools.unwrap_or(args.calls.len()).max(1);
+        let calls = args
+            .calls
+            .iter()
+            .zip(approvals)
+            .map(|(call, approval)| {
+                let future: ToolFuture = match (&mut self.structured_output, approval) {
+                    (Some(capture), _) if capture.tool_name == call.name => {
+                        let result = capture.accept(&call.input);
+                        Box::pin(async move { result })
+                    }
+                    (_, Err(e)) => Box::pin(async move { Err(e) }),
+                    _ => {
+                        let context =
+                            self.tool_context(&call.tool_use_id, call.agent_state.as_deref());
+                        self.dispatch(&call.name, &call.input, &context)
+                    }
+                };
+                let sequential = self.sequential_tools.contains(&call.name);
+                let exclusive = Arc::clone(&exclusive);
+                async move {
+                    if sequential {
+                        let _guard = exclusive.write().await;
+                        future.await
+                    } else {
+                        let _guard = exclusive.read().await;
+                        future.await
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        futures_util::stream::iter(calls)
+            .buffered(limit)
+            .collect()
+            .await
     }
 }
 
@@ -186,8 +572,18 @@ pub struct AgentBuilder {
     tools: Vec<ToolSpec>,
     handlers: HashMap<String, ToolHandlerFn>,
     tool_dispatch: Option<ToolDispatchFn>,
+    tool_options: HashMap<String, ToolOptions>,
+    sequential_tools: HashSet<String>,
+    max_parallel_tools: Option<usize>,
+    structured_output_retries: usize,
+    interrupts: interrupt::Interrupts,
+    tool_policy: ToolPolicy,
+    invocation_timeout: Option<Duration>,
+    limits: StoreLimits,
     log_handler: Option<LogHandlerFn>,
+    hooks: HookRegistry,
     trace_context: Option<String>,
+    session: Option<SessionConfig>,
     use_jit: bool,
 }
 
@@ -217,6 +613,8 @@ impl AgentBuilder {
     }
 
     /// Register a tool with a name, description, JSON schema, and handler.
+    /// Inputs that do not match the schema are returned to the model as an
+    /// error listing each violation, without calling the handler.
     pub fn tool(
         mut self,
         name: impl Into<String>,
@@ -230,7 +628,172 @@ impl AgentBuilder {
             description: description.into(),
             input_schema: serde_json::to_string(&input_schema).unwrap_or_default(),
         });
-        self.handlers.insert(name, Box::new(handler));
+        self.handlers.insert(
+            name,
+            Arc::new(move |input: &str, _context: &ToolContext| -> ToolFuture {
+                let result = handler(input);
+                Box::pin(async move { result })
+            }),
+        );
+        self
+    }
+
+    /// Like [`tool`](Self::tool), with a handler that also receives the
+    /// call's [`ToolContext`].
+    pub fn tool_with_context(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: serde_json::Value,
+        handler: impl Fn(&str, &ToolContext) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        let name = name.into();
+        self.tools.push(ToolSpec {
+            name: name.clone(),
+            description: description.into(),
+            input_schema: serde_json::to_string(&input_schema).unwrap_or_default(),
+        });
+        self.handlers.insert(
+            name,
+            Arc::new(move |input: &str, context: &ToolContext| -> ToolFuture {
+                let result = handler(input, context);
+                Box::pin(async move { result })
+            }),
+        );
+        self
+    }
+
+    /// Register a tool whose handler returns a future, for tools that do I/O.
+    /// The host awaits the future without blocking the tokio worker.
+    pub fn async_tool<F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: serde_json::Value,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        let name = name.into();
+        self.tools.push(ToolSpec {
+            name: name.clone(),
+            description: description.into(),
+            input_schema: serde_json::to_string(&input_schema).unwrap_or_default(),
+        });
+        self.handlers.insert(
+            name,
+            Arc::new(move |input: &str, _context: &ToolContext| -> ToolFuture {
+                Box::pin(handler(input.to_string()))
+            }),
+        );
+        self
+    }
+
+    /// Like [`async_tool`](Self::async_tool), with a handler that also
+    /// receives the call's [`ToolContext`]. Watch
+    /// [`ToolContext::cancellation`] to stop work when the invocation is
+    /// cancelled.
+    pub fn async_tool_with_context<F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: serde_json::Value,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(String, ToolContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        let name = name.into();
+        self.tools.push(ToolSpec {
+            name: name.clone(),
+            description: description.into(),
+            input_schema: serde_json::to_string(&input_schema).unwrap_or_default(),
+        });
+        self.handlers.insert(
+            name,
+            Arc::new(move |input: &str, context: &ToolContext| -> ToolFuture {
+                Box::pin(handler(input.to_string(), context.clone()))
+            }),
+        );
+        self
+    }
+
+    /// Register a typed [`Tool`]. Its input schema is generated from `T::Input`.
+    pub fn with_tool<T: Tool>(mut self, tool: T) -> Self {
+        let spec = tool_spec(&tool);
+        self.handlers
+            .insert(spec.name.clone(), tool::into_handler(tool));
+        self.tools.push(spec);
+        self
+    }
+
+    /// Register another agent as a tool. The model calls it with a prompt and
+    /// receives the sub-agent's final text. Calls are serialized, and the
+    /// sub-agent keeps its conversation history between them.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use strands::Agent;
+    ///
+    /// let researcher = Agent::builder()
+    ///     .system_prompt("You research topics thoroughly and cite sources.")
+    ///     .build()
+    ///     .await?;
+    ///
+    /// let mut agent = Agent::builder()
+    ///     .agent_tool("research", "Research a topic in depth", researcher)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn agent_tool(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        sub_agent: Agent,
+    ) -> Self {
+        let name = name.into();
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "prompt": {
+                    "type": "string",
+                    "description": "The request for the agent.",
+                },
+            },
+            "required": ["prompt"],
+        });
+        self.tools.push(ToolSpec {
+            name: name.clone(),
+            description: description.into(),
+            input_schema: schema.to_string(),
+        });
+
+        let sub_agent = Arc::new(tokio::sync::Mutex::new(sub_agent));
+        self.handlers.insert(
+            name,
+            Arc::new(move |input: &str, _context: &ToolContext| -> ToolFuture {
+                let sub_agent = Arc::clone(&sub_agent);
+                let prompt = serde_json::from_str::<serde_json::Value>(input)
+                    .ok()
+                    .and_then(|input| input["prompt"].as_str().map(str::to_string));
+                Box::pin(async move {
+                    let prompt = prompt.ok_or("prompt is required")?;
+                    let result = sub_agent
+                        .lock()
+                        .await
+                        .invoke(&prompt)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    Ok(ToolResult::success().text(result.text).to_json())
+                })
+            }),
+        );
         self
     }
 
@@ -240,19 +803,176 @@ impl AgentBuilder {
         self
     }
 
+    /// Mark a tool as sequential-only. When the model requests several tools in
+    /// one turn, a call to a sequential-only tool runs alone, with no other
+    /// call from the batch in flight.
+    pub fn sequential_tool(mut self, name: impl Into<String>) -> Self {
+        self.sequential_tools.insert(name.into());
+        self
+    }
+
+    /// Bound, retry and circuit-break calls to the named tool.
+    pub fn tool_options(mut self, name: impl Into<String>, options: ToolOptions) -> Self {
+        self.tool_options.insert(name.into(), options);
+        self
+    }
+
+    /// Cap how many tools from a single batch run concurrently. Unlimited by default.
+    pub fn max_parallel_tools(mut self, max: usize) -> Self {
+        self.max_parallel_tools = Some(max);
+        self
+    }
+
+    /// How many times [`Agent::structured_output`] re-prompts the model after
+    /// invalid output. Defaults to 2.
+    pub fn structured_output_retries(mut self, retries: usize) -> Self {
+        self.structured_output_retries = retries;
+        self
+    }
+
+    /// Require approval before each call to the named tool. The call raises a
+    /// `tool_approval` [`Interrupt`] and runs only if the response is `true`.
+    pub fn interrupt_before(mut self, tool: impl Into<String>) -> Self {
+        self.interrupts.tools.insert(tool.into());
+        self
+    }
+
+    /// Answer interrupts as they are raised. Returning `None` leaves the
+    /// interrupt unanswered, so [`Agent::invoke`] stops and reports it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use strands::Agent;
+    ///
+    /// let mut agent = Agent::builder()
+    ///     .interrupt_before("delete_file")
+    ///     .interrupt_handler(|interrupt| async move {
+    ///         println!("approve {}?", interrupt.reason);
+    ///         Some(serde_json::Value::Bool(true))
+    ///     })
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_handler<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(Interrupt) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<serde_json::Value>> + Send + 'static,
+    {
+        self.interrupts.handler = Some(Arc::new(move |interrupt| Box::pin(handler(interrupt))));
+        self
+    }
+
+    /// Enforce `policy` on every tool call before it is dispatched. Calls the
+    /// policy asks about raise a `tool_approval` [`Interrupt`], like
+    /// [`interrupt_before`](Self::interrupt_before).
+    pub fn tool_policy(mut self, policy: ToolPolicy) -> Self {
+        self.tool_policy = policy;
+        self
+    }
+
+    /// Bound each invocation to `timeout` of wall-clock time. An invocation
+    /// that runs over fails with [`Error::Timeout`]; the agent is then rebuilt
+    /// with the history it had before that invocation. Unlimited by default.
+    pub fn invocation_timeout(mut self, timeout: Duration) -> Self {
+        self.invocation_timeout = Some(timeout);
+        self
+    }
+
+    /// Cap the agent's memory, instances, tables and per-invocation fuel.
+    /// Exceeding a cap fails with [`Error::LimitExceeded`] and rolls the agent
+    /// back like a timeout.
+    pub fn limits(mut self, limits: StoreLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Run lifecycle hooks synchronously as the agent works. Replaces any
+    /// hooks added before.
+    pub fn hooks(mut self, hooks: HookRegistry) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Add a single hook callback for `E`. See [`HookRegistry::add_callback`].
+    pub fn hook<E: hooks::HookEvent>(
+        mut self,
+        callback: impl Fn(&mut E) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.add_callback(callback);
+        self
+    }
+
     /// Set the W3C traceparent header for distributed tracing propagation.
     pub fn trace_context(mut self, ctx: impl Into<String>) -> Self {
         self.trace_context = Some(ctx.into());
         self
     }
 
-    /// Set a single dispatch function for all tool calls.
+    /// Persist conversation snapshots to the given storage backend.
+    /// The guest restores the latest snapshot for the session ID on construction.
+    /// A [`StorageConfig::File`] directory is created if missing and is the
+    /// only host directory the guest can access.
+    pub fn session(mut self, config: SessionConfig) -> Self {
+        self.session = Some(config);
+        self
+    }
+
+    /// Set a single dispatch function for tool calls. Tools registered with
+    /// their own handler are still called directly.
     /// Receives `(tool_name, input_json, tool_use_id)`.
     pub fn tool_dispatch_fn(
-        mut self,
+        self,
         f: impl Fn(&str, &str, &str) -> Result<String, String> + Send + Sync + 'static,
     ) -> Self {
-        self.tool_dispatch = Some(Box::new(f));
+        self.tool_dispatch_fn_with_context(move |name, input, context| {
+            f(name, input, &context.tool_use_id)
+        })
+    }
+
+    /// Like [`tool_dispatch_fn`](Self::tool_dispatch_fn), with a function
+    /// that receives the call's [`ToolContext`] in place of its tool use ID.
+    pub fn tool_dispatch_fn_with_context(
+        mut self,
+        f: impl Fn(&str, &str, &ToolContext) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.tool_dispatch = Some(Arc::new(
+            move |name: &str, input: &str, context: &ToolContext| -> ToolFuture {
+                let result = f(name, input, context);
+                Box::pin(async move { result })
+            },
+        ));
+        self
+    }
+
+    /// Set a single async dispatch function for tool calls. Tools registered
+    /// with their own handler are still called directly.
+    /// Receives owned `(tool_name, input_json, tool_use_id)`.
+    pub fn async_tool_dispatch_fn<F, Fut>(self, f: F) -> Self
+    where
+        F: Fn(String, String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.async_tool_dispatch_fn_with_context(move |name, input, context| {
+            f(name, input, context.tool_use_id)
+        })
+    }
+
+    /// Like [`async_tool_dispatch_fn`](Self::async_tool_dispatch_fn), with a
+    /// function that receives the call's [`ToolContext`] in place of its tool
+    /// use ID.
+    pub fn async_tool_dispatch_fn_with_context<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(String, String, ToolContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.tool_dispatch = Some(Arc::new(
+            move |name: &str, input: &str, context: &ToolContext| -> ToolFuture {
+                Box::pin(f(name.to_string(), input.to_string(), context.clone()))
+            },
+        ));
         self
     }
 
@@ -270,13 +990,18 @@ impl AgentBuilder {
         mut self,
         f: impl Fn(&str, &str, Option<&str>) + Send + Sync + 'static,
     ) -> Self {
-        self.log_handler = Some(Box::new(f));
+        self.log_handler = Some(Arc::new(f));
         self
     }
 
     /// Build the agent, loading and instantiating the WASM component.
     pub async fn build(self) -> Result<Agent> {
-        Agent::from_builder(self).await
+        let runtime = if self.use_jit {
+            Runtime::Jit
+        } else {
+            Runtime::Aot
+        };
+        Agent::from_builder(self, runtime).await
     }
 }
 
@@ -314,6 +1039,17 @@ pub struct Agent {
     store: Store<HostState>,
     instance: bindings::Agent,
     wit_agent: ResourceAny,
+    tools: Vec<ToolSpec>,
+    structured_output_retries: usize,
+    abandoned_streams: stream::AbandonedStreams,
+    config: WitAgentConfig,
+    runtime: Runtime,
+    invocation_timeout: Option<Duration>,
+    /// History at the start of the current invocation, so an invocation that
+    /// leaves the instance unusable can be rolled back.
+    checkpoint: Option<String>,
+    /// An invocation stopped on unanswered interrupts, for [`Agent::resume`].
+    suspended: Option<interrupt::Suspended>,
 }
 
 impl Agent {
@@ -327,77 +1063,47 @@ impl Agent {
             tools: Vec::new(),
             handlers: HashMap::new(),
             tool_dispatch: None,
+            tool_options: HashMap::new(),
+            sequential_tools: HashSet::new(),
+            max_parallel_tools: None,
+            structured_output_retries: 2,
+            interrupts: Default::default(),
+            tool_policy: ToolPolicy::new(),
+            invocation_timeout: None,
+            limits: StoreLimits::default(),
             log_handler: None,
+            hooks: HookRegistry::new(),
             trace_context: None,
+            session: None,
             use_jit: false,
         }
     }
 
-    async fn from_builder(builder: AgentBuilder) -> Result<Self> {
+    async fn from_builder(builder: AgentBuilder, runtime: Runtime) -> Result<Self> {
+        let tool_specs = builder.tools.clone();
         let tools = if builder.tools.is_empty() {
             None
         } else {
             Some(builder.tools)
         };
 
-        let tool_dispatch = if let Some(dispatch) = builder.tool_dispatch {
-            Some(dispatch)
-        } else if !builder.handlers.is_empty() {
-            let handlers = builder.handlers;
-            Some(Box::new(
-                move |name: &str, input: &str, _tool_use_id: &str| -> Result<String, String> {
-                    match handlers.get(name) {
-                        Some(handler) => handler(input),
-                        None => Err(format!("unknown tool: {name}")),
-                    }
-                },
-            ) as ToolDispatchFn)
-        } else {
-            None
-        };
+        let tool_guard = (!builder.tool_options.is_empty())
+            .then(|| resilience::ToolGuard::new(builder.tool_options));
 
         let host_state = HostState {
-            ctx: WasiCtxBuilder::new().inherit_stdio().inherit_env().build(),
-            http: WasiHttpCtx::new(),
-            table: ResourceTable::new(),
-            tool_dispatch,
+            tool_handlers: Arc::new(builder.handlers),
+            tool_dispatch: builder.tool_dispatch,
+            tool_guard,
+            tool_inputs: Arc::new(validation::InputValidators::new(&tool_specs)),
+            sequential_tools: builder.sequential_tools,
+            max_parallel_tools: builder.max_parallel_tools,
+            interrupts: builder.interrupts,
+            tool_policy: builder.tool_policy,
             log_handler: builder.log_handler,
-        };
-
-        let (mut store, instance) = if builder.use_jit {
-            // JIT mode: create everything fresh (dev mode, no caching).
-            let mut wasm_config = Config::new();
-            wasm_config.async_support(true);
-            wasm_config.wasm_component_model(true);
-            wasm_config.max_wasm_stack(32 * 1024 * 1024);
-            wasm_config.async_stack_size(64 * 1024 * 1024);
-            let engine = Engine::new(&wasm_config)?;
-            let wasm_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-                .join("../strands-wasm/dist/strands-agent.wasm");
-            let component = Component::from_file(&engine, &wasm_path)
-                .with_context(|| format!("JIT: failed to load {}", wasm_path.display()))?;
-            let mut linker = Linker::<HostState>::new(&engine);
-            wasmtime_wasi::p2::add_to_linker_async(&mut linker)?;
-            wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
-            bindings::strands::agent::tool_provider::add_to_linker::<HostState, HasSelf<HostState>>(
-                &mut linker,
-                |state: &mut HostState| state,
-            )?;
-            bindings::strands::agent::host_log::add_to_linker::<HostState, HasSelf<HostState>>(
-                &mut linker,
-                |state: &mut HostState| state,
-            )?;
-            let mut store = Store::new(&engine, host_state);
-            let instance =
-                bindings::Agent::instantiate_async(&mut store, &component, &linker).await?;
-            (store, instance)
-        } else {
-            // AOT mode: use the process-wide cache.
-            let (engine, component, linker) = AOT_CACHE.get_or_init(init_aot_cache);
-            let mut store = Store::new(engine, host_state);
-            let instance =
-                bindings::Agent::instantiate_async(&mut store, component, linker).await?;
-            (store, instance)
+            hooks: builder.hooks,
+            trace_context: builder.trace_context.clone(),
+            limiter: limits::Limiter::new(builder.limits),
+            ..HostState::new()
         };
 
         // The guest is bundled with --platform=browser so the AWS SDK can't
@@ -412,24 +1118,108 @@ impl Agent {
             system_prompt_blocks: builder.system_prompt_blocks,
             tools,
             trace_context: builder.trace_context,
-            session: None,
+            session: builder.session,
         };
 
-        let iface = instance.strands_agent_api();
-        let wit_agent = iface.agent().call_constructor(&mut store, &config).await?;
+        let (store, instance, wit_agent) = instantiate(host_state, &runtime, &config).await?;
 
         Ok(Self {
             store,
             instance,
             wit_agent,
+            tools: tool_specs,
+            structured_output_retries: builder.structured_output_retries,
+            abandoned_streams: Default::default(),
+            config,
+            runtime,
+            invocation_timeout: builder.invocation_timeout,
+            checkpoint: None,
+            suspended: None,
         })
     }
 
+    /// Replace an instance that hit a deadline or limit, or was left inside a
+    /// call by a dropped stream, with a fresh one, restoring the history
+    /// checkpointed when the last invocation started.
+    async fn recover(&mut self) -> Result<()> {
+        let mid_call = std::mem::take(
+            &mut self
+                .abandoned_streams
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .mid_call,
+        );
+        if !self.store.data().poisoned && !mid_call {
+            return Ok(());
+        }
+        let host_state = self.store.data_mut().renew();
+        let (store, instance, wit_agent) =
+            instantiate(host_state, &self.runtime, &self.config).await?;
+        self.store = store;
+        self.instance = instance;
+        self.wit_agent = wit_agent;
+        self.suspended = None;
+        *self
+            .abandoned_streams
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Default::default();
+
+        if let Some(json) = self.checkpoint.take() {
+            let args = bindings::exports::strands::agent::api::SetMessagesArgs { json };
+            self.instance
+                .strands_agent_api()
+                .agent()
+                .call_set_messages(&mut self.store, self.wit_agent, &args)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Create an independent agent with the same configuration and a copy of
+    /// this agent's conversation history.
+    ///
+    /// The fork shares tool handlers and callbacks with this agent but not its
+    /// session, so the two never overwrite each other's snapshots. Forks of a
+    /// pooled agent run outside the pool and do not use its slots.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example(mut agent: strands::Agent) -> anyhow::Result<()> {
+    /// let mut branch = agent.fork().await?;
+    /// let terse = agent.invoke("Summarize that in one sentence.").await?;
+    /// let detailed = branch.invoke("Summarize that in detail.").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fork(&mut self) -> Result<Agent> {
+        let history = self.get_messages().await?;
+        let config = WitAgentConfig {
+            session: None,
+            ..self.config.clone()
+        };
+        let host_state = self.store.data().fork();
+        let runtime = self.runtime.unpooled();
+        let (store, instance, wit_agent) = instantiate(host_state, &runtime, &config).await?;
+
+        let mut fork = Agent {
+            store,
+            instance,
+            wit_agent,
+            tools: self.tools.clone(),
+            structured_output_retries: self.structured_output_retries,
+            abandoned_streams: Default::default(),
+            config,
+            runtime,
+            invocation_timeout: self.invocation_timeout,
+            checkpoint: None,
+            suspended: None,
+        };
+        fork.set_messages(&history).await?;
+        Ok(fork)
+    }
+
     /// Stream events from the agent for the given input.
-    pub async fn stream(
-        &mut self,
-        input: &str,
-    ) -> Result<impl futures_core::Stream<Item = Result<StreamEvent>> + '_> {
+    pub async fn stream(&mut self, input: &str) -> Result<ResponseStream<'_>> {
         self.stream_with_options(input, None, None).await
     }
 
@@ -439,95 +1229,129 @@ impl Agent {
         input: &str,
         tools: Option<Vec<ToolSpec>>,
         tool_choice: Option<String>,
-    ) -> Result<impl futures_core::Stream<Item = Result<StreamEvent>> + '_> {
-        let Self {
-            store,
-            instance,
-            wit_agent,
-        } = self;
-        let api = instance.strands_agent_api();
-        let args = bindings::exports::strands::agent::api::StreamArgs {
-            input: input.to_string(),
-            tools,
-            tool_choice,
-        };
-        let handle = api
-            .agent()
-            .call_generate(&mut *store, *wit_agent, &args)
+    ) -> Result<ResponseStream<'_>> {
+        let handle = self
+            .start_stream_with_options(input, tools, tool_choice)
             .await?;
-
-        Ok(async_stream::stream! {
-            loop {
-                match api
-                    .response_stream()
-                    .call_read_next(&mut *store, handle)
-                    .await
-                {
-                    Ok(Some(events)) => {
-                        for event in events {
-                            yield Ok(event);
-                        }
-                    }
-                    Ok(None) => break,
-                    Err(e) => {
-                        yield Err(e);
-                        break;
-                    }
-                }
-            }
-        })
+        Ok(ResponseStream::new(self, handle))
     }
 
     /// Invoke the agent and collect the full response.
     pub async fn invoke(&mut self, input: &str) -> Result<AgentResult> {
-        let Self {
-            store,
-            instance,
-            wit_agent,
-        } = self;
-        let api = instance.strands_agent_api();
-        let args = bindings::exports::strands::agent::api::StreamArgs {
-            input: input.to_string(),
-            tools: None,
-            tool_choice: None,
-        };
-        let handle = api
-            .agent()
-            .call_generate(&mut *store, *wit_agent, &args)
-            .await?;
+        self.invoke_with_events(input, None, |_| {}).await
+    }
 
-        let mut text = String::new();
-        let mut stop_data = None;
+    /// [`invoke`](Self::invoke) with an optional tool override, passing each
+    /// event to `on_event` as it arrives.
+    pub(crate) async fn invoke_with_events(
+        &mut self,
+        input: &str,
+        tools: Option<Vec<ToolSpec>>,
+        on_event: impl FnMut(&StreamEvent) + Send,
+    ) -> Result<AgentResult> {
+        let handle = self.start_stream_with_options(input, tools, None).await?;
+        self.collect(handle, result::Collector::default(), on_event)
+            .await
+    }
 
-        while let Some(events) = api
-            .response_stream()
-            .call_read_next(&mut *store, handle)
-            .await?
-        {
-            for event in events {
-                match event {
-                    StreamEvent::TextDelta(delta) => text.push_str(&delta),
-                    StreamEvent::Stop(data) => stop_data = Some(data),
-                    StreamEvent::Error(err) => {
-                        return Err(anyhow::anyhow!("agent error: {err}"));
-                    }
-                    _ => {}
-                }
+    /// Read a stream to the end, answering interrupts with the interrupt
+    /// handler. If any are left unanswered the stream is kept for
+    /// [`resume`](Self::resume) and the result reports them.
+    async fn collect(
+        &mut self,
+        handle: ResourceAny,
+        mut collector: result::Collector,
+        mut on_event: impl FnMut(&StreamEvent) + Send,
+    ) -> Result<AgentResult> {
+        let handler = self.store.data().interrupts.handler.clone();
+        let mut stream = ResponseStream::new(self, handle);
+
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            on_event(&event);
+            let raised = match &event {
+                StreamEvent::Interrupt(payload) => Some(
+                    Interrupt::from_event(payload)
+                        .context("failed to parse interrupt")
+                        .map_err(Error::Runtime)?,
+                ),
+                _ => None,
+            };
+            if let Some(err) = collector.record(event) {
+                return Err(Error::from_guest(err));
+            }
+            if let (Some(interrupt), Some(handler)) = (raised, &handler)
+                && let Some(response) = handler(interrupt.clone()).await
+            {
+                stream.respond(interrupt.respond(response)).await?;
+                collector.answered(&interrupt.id);
             }
         }
 
-        let stop = stop_data.unwrap_or(StopData {
-            reason: StopReason::EndTurn,
-            usage: None,
-            metrics: None,
-        });
+        if !collector.unanswered().is_empty() {
+            let handle = stream.suspend();
+            self.suspended = Some(interrupt::Suspended {
+                handle,
+                collector: collector.clone(),
+            });
+            return Ok(collector.finish(None));
+        }
+        stream.close().await?;
+        let history = self.messages().await?;
+        Ok(collector.finish(Some(history)))
+    }
 
-        Ok(AgentResult {
-            text,
-            stop_reason: stop.reason,
-            usage: stop.usage,
-            metrics: stop.metrics,
-        })
+    /// Invoke the agent with a deadline for this call only, overriding
+    /// [`AgentBuilder::invocation_timeout`].
+    pub async fn invoke_with_timeout(
+        &mut self,
+        input: &str,
+        timeout: Duration,
+    ) -> Result<AgentResult> {
+        let default = self.invocation_timeout.replace(timeout);
+        let result = self.invoke(input).await;
+        self.invocation_timeout = default;
+        result
+    }
+
+    /// Continue an interrupted invocation with answers to its interrupts.
+    ///
+    /// The invocation picks up where it stopped, with the same stream, and
+    /// the calls that raised the interrupts run or are rejected according to
+    /// the answers. If some interrupts are still unanswered the invocation
+    /// stays stopped and the result reports them again. Starting another
+    /// invocation instead abandons the interrupted one.
+    pub async fn resume(
+        &mut self,
+        responses: impl IntoIterator<Item = InterruptResponse>,
+    ) -> Result<AgentResult> {
+        let Some(mut suspended) = self.suspended.take() else {
+            return Err(Error::Runtime(anyhow::anyhow!(
+                "no interrupted invocation to resume"
+            )));
+        };
+        let mut responses: HashMap<_, _> = responses
+            .into_iter()
+            .map(|r| (r.interrupt_id, r.response))
+            .collect();
+        for interrupt in suspended.collector.unanswered().to_vec() {
+            if let Some(response) = responses.remove(&interrupt.id) {
+                let payload = interrupt.respond(response).to_payload();
+                self.respond(suspended.handle, &payload).await?;
+                suspended.collector.answered(&interrupt.id);
+            }
+        }
+        if !suspended.collector.unanswered().is_empty() {
+            let result = suspended.collector.clone().finish(None);
+            self.suspended = Some(suspended);
+            return Ok(result);
+        }
+
+        self.store.data_mut().invocation_deadline = self
+            .invocation_timeout
+            .map(|timeout| Instant::now() + timeout);
+        self.collect(suspended.handle, suspended.collector, |_| {})
+            .await
     }
 }
 
@@ -544,10 +1368,35 @@ impl Agent {
         tools: Option<Vec<ToolSpec>>,
         tool_choice: Option<String>,
     ) -> Result<ResourceAny> {
+        let deadline = self
+            .invocation_timeout
+            .map(|timeout| Instant::now() + timeout);
+        self.start_stream_until(input, tools, tool_choice, deadline)
+            .await
+    }
+
+    /// Begin a new response stream that must finish by `deadline`.
+    async fn start_stream_until(
+        &mut self,
+        input: &str,
+        tools: Option<Vec<ToolSpec>>,
+        tool_choice: Option<String>,
+        deadline: Option<Instant>,
+    ) -> Result<ResourceAny> {
+        self.recover().await?;
+        self.abandon_suspended();
+        self.release_abandoned_streams().await?;
+        self.store.data_mut().interrupts.clear();
+
+        self.checkpoint = Some(self.get_messages().await?);
+        self.store.data_mut().invocation_deadline = deadline;
+        self.store.data_mut().invocation = context::Invocation::start();
+        limits::reset_fuel(&mut self.store)?;
         let Self {
             store,
             instance,
             wit_agent,
+            ..
         } = self;
         let api = instance.strands_agent_api();
         let args = bindings::exports::strands::agent::api::StreamArgs {
@@ -555,74 +1404,310 @@ impl Agent {
             tools,
             tool_choice,
         };
-        api.agent()
+        Ok(api
+            .agent()
             .call_generate(&mut *store, *wit_agent, &args)
-            .await
+            .await?)
     }
 
     /// Pull the next batch of events from the stream.
     pub async fn next_events(&mut self, handle: ResourceAny) -> Result<Option<Vec<StreamEvent>>> {
-        let api = self.instance.strands_agent_api();
-        api.response_stream()
-            .call_read_next(&mut self.store, handle)
-            .await
+        Ok(deadline::read_next(&mut self.store, &self.instance, handle).await?)
     }
 
-    /// Send a response to an interrupt on the given stream.
+    /// Answer an interrupt raised on the given stream. `payload` is JSON
+    /// `{"interruptId": ..., "response": ...}`; see
+    /// [`InterruptResponse`].
     pub async fn respond(&mut self, handle: ResourceAny, payload: &str) -> Result<()> {
         let api = self.instance.strands_agent_api();
         let args = bindings::exports::strands::agent::api::RespondArgs {
             payload: payload.to_string(),
         };
-        api.response_stream()
+        Ok(api
+            .response_stream()
             .call_respond(&mut self.store, handle, &args)
-            .await
+            .await?)
     }
 
-    /// Cancel a running stream.
+    /// Cancel a running stream. Fires [`ToolContext::cancellation`] for the
+    /// tools it called.
     pub async fn cancel_stream(&mut self, handle: ResourceAny) -> Result<()> {
+        self.store.data().invocation.cancellation.cancel();
         let api = self.instance.strands_agent_api();
-        api.response_stream()
+        Ok(api
+            .response_stream()
             .call_cancel(&mut self.store, handle)
-            .await
+            .await?)
     }
 
     /// Drop (destroy) a response-stream resource, releasing WASM-side state.
     pub async fn drop_stream(&mut self, handle: ResourceAny) -> Result<()> {
-        handle.resource_drop_async(&mut self.store).await
+        Ok(handle.resource_drop_async(&mut self.store).await?)
+    }
+
+    /// Host-local scratch values shared with this agent's tools through
+    /// [`ToolContext::scratch`]. Not part of the conversation or the session,
+    /// and not visible to the guest. A [`fork`](Self::fork) starts with a copy.
+    pub fn scratch(&self) -> &Scratch {
+        &self.store.data().scratch
+    }
+
+    /// Report memory and fuel consumption.
+    pub fn resource_usage(&self) -> ResourceUsage {
+        limits::usage(&self.store)
     }
 
     /// Get the agent's conversation history as a JSON string.
     pub async fn get_messages(&mut self) -> Result<String> {
+        self.recover().await?;
         let api = self.instance.strands_agent_api();
-        api.agent()
+        Ok(api
+            .agent()
             .call_get_messages(&mut self.store, self.wit_agent)
-            .await
+            .await?)
+    }
+
+    /// Get the agent's conversation history.
+    pub async fn messages(&mut self) -> Result<Vec<Message>> {
+        let json = self.get_messages().await?;
+        serde_json::from_str(&json)
+            .context("failed to parse conversation history")
+            .map_err(Error::Runtime)
+    }
+
+    /// Replace the agent's conversation history.
+    pub async fn set_message_history(&mut self, messages: &[Message]) -> Result<()> {
+        let json = serde_json::to_string(messages)
+            .context("failed to serialize conversation history")
+            .map_err(Error::Runtime)?;
+        self.set_messages(&json).await
     }
 
     /// Set the agent's conversation history from a JSON string.
     pub async fn set_messages(&mut self, json: &str) -> Result<()> {
+        self.recover().await?;
         let api = self.instance.strands_agent_api();
         let args = bindings::exports::strands::agent::api::SetMessagesArgs {
             json: json.to_string(),
         };
-        api.agent()
+        Ok(api
+            .agent()
             .call_set_messages(&mut self.store, self.wit_agent, &args)
+            .await?)
+    }
+
+    /// The tools the model can call, in the order they were registered.
+    pub fn list_tools(&self) -> &[ToolSpec] {
+        &self.tools
+    }
+
+    /// Register a tool on the live agent. The model can call it from the
+    /// next model call on; the conversation is kept. A tool with the same
+    /// name is replaced. Like
+    /// [`AgentBuilder::async_tool_with_context`], the handler receives the
+    /// call's [`ToolContext`].
+    ///
+    /// Tools added to a pooled agent stay registered when it returns to the
+    /// pool.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example(mut agent: strands::Agent) -> anyhow::Result<()> {
+    /// agent
+    ///     .add_tool(
+    ///         "lookup",
+    ///         "Look up a record by id.",
+    ///         serde_json::json!({
+    ///             "type": "object",
+    ///             "properties": {"id": {"type": "string"}},
+    ///             "required": ["id"],
+    ///         }),
+    ///         |input, _ctx| async move { Ok(input) },
+    ///     )
+    ///     .await?;
+    /// agent.invoke("Look up record 42.").await?;
+    /// agent.remove_tool("lookup").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_tool<F, Fut>(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: serde_json::Value,
+        handler: F,
+    ) -> Result<()>
+    where
+        F: Fn(String, ToolContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.recover().await?;
+        let spec = ToolSpec {
+            name: name.into(),
+            description: description.into(),
+            input_schema: serde_json::to_string(&input_schema).unwrap_or_default(),
+        };
+        let handler: ToolHandlerFn =
+            Arc::new(move |input: &str, context: &ToolContext| -> ToolFuture {
+                Box::pin(handler(input.to_string(), context.clone()))
+            });
+        let mut tools = self.tools.clone();
+        match tools.iter_mut().find(|tool| tool.name == spec.name) {
+            Some(tool) => *tool = spec.clone(),
+            None => tools.push(spec.clone()),
+        }
+        self.set_tools(tools).await?;
+        self.store.data_mut().register_tool(&spec, handler);
+        Ok(())
+    }
+
+    /// Unregister a tool. Returns whether the agent had it.
+    pub async fn remove_tool(&mut self, name: &str) -> Result<bool> {
+        self.recover().await?;
+        let Some(index) = self.tools.iter().position(|tool| tool.name == name) else {
+            return Ok(false);
+        };
+        let mut tools = self.tools.clone();
+        tools.remove(index);
+        self.set_tools(tools).await?;
+        self.store.data_mut().unregister_tool(name);
+        Ok(true)
+    }
+
+    /// Replace the guest's tools, then keep them in `self.tools` and in the
+    /// config a rebuilt or forked instance is constructed from. Nothing
+    /// changes if the guest call fails.
+    async fn set_tools(&mut self, tools: Vec<ToolSpec>) -> Result<()> {
+        let api = self.instance.strands_agent_api();
+        let args = bindings::exports::strands::agent::api::SetToolsArgs {
+            tools: tools.clone(),
+        };
+        api.agent()
+            .call_set_tools(&mut self.store, self.wit_agent, &args)
+            .await?;
+        self.config.tools = (!tools.is_empty()).then(|| tools.clone());
+        self.tools = tools;
+        Ok(())
+    }
+
+    /// Save a snapshot of the conversation to the configured session storage.
+    pub async fn save_session(&mut self) -> Result<()> {
+        self.recover().await?;
+        let api = self.instance.strands_agent_api();
+        api.agent()
+            .call_save_session(&mut self.store, self.wit_agent)
+            .await?
+            .map_err(Error::from_guest)
+    }
+
+    /// List the snapshot IDs stored for the configured session.
+    pub async fn list_snapshots(&mut self) -> Result<Vec<String>> {
+        self.recover().await?;
+        let api = self.instance.strands_agent_api();
+        api.agent()
+            .call_list_snapshots(&mut self.store, self.wit_agent)
+            .await?
+            .map_err(Error::from_guest)
+    }
+
+    /// Delete all persisted state for the configured session: every
+    /// snapshot, the latest snapshot and the manifest.
+    pub async fn delete_session(&mut self) -> Result<()> {
+        self.recover().await?;
+        let api = self.instance.strands_agent_api();
+        api.agent()
+            .call_delete_session(&mut self.store, self.wit_agent)
+            .await?
+            .map_err(Error::from_guest)
+    }
+}
+
+/// Instantiate the component in a new store and construct the guest agent.
+async fn instantiate(
+    mut host_state: HostState,
+    runtime: &Runtime,
+    config: &WitAgentConfig,
+) -> Result<(Store<HostState>, bindings::Agent, ResourceAny)> {
+    host_state.ctx = wasi_ctx(config.session.as_ref())?;
+    let metered = host_state.limiter.limits.fuel_per_invocation.is_some();
+    let load_error = |e: wasmtime::Error| Error::ComponentLoad(format!("{e:#}"));
+    let (mut store, instantiated) = match runtime {
+        Runtime::Jit => instantiate_jit(host_state, metered)
             .await
+            .map_err(load_error)?,
+        Runtime::Aot => {
+            let (engine, component, linker) = aot_cache(metered)?;
+            let mut store = new_store(engine, host_state).map_err(load_error)?;
+            let instance = bindings::Agent::instantiate_async(&mut store, component, linker).await;
+            (store, instance)
+        }
+        Runtime::Pooled(pre) => {
+            let mut store = new_store(pre.engine(), host_state).map_err(load_error)?;
+            let instance = pre.instantiate_async(&mut store).await;
+            (store, instance)
+        }
+    };
+    let instance = instantiated.map_err(|e| {
+        let limit = store.data_mut().limiter.instantiation_failed(&e);
+        limit.map_or_else(|| load_error(e), Error::LimitExceeded)
+    })?;
+
+    let iface = instance.strands_agent_api();
+    let wit_agent = iface.agent().call_constructor(&mut store, config).await?;
+    Ok((store, instance, wit_agent))
+}
+
+/// WASI context for an instance. A file-storage session directory is created
+/// if needed and preopened at the same path, so the guest can persist
+/// snapshots there; the guest sees no other part of the host filesystem.
+fn wasi_ctx(session: Option<&SessionConfig>) -> Result<WasiCtx> {
+    let mut builder = WasiCtxBuilder::new();
+    builder.inherit_stdio().inherit_env();
+    if let Some(SessionConfig {
+        storage: StorageConfig::File(file),
+        ..
+    }) = session
+    {
+        std::fs::create_dir_all(&file.base_dir)
+            .with_context(|| format!("failed to create session directory {}", file.base_dir))
+            .map_err(Error::Runtime)?;
+        builder
+            .preopened_dir(
+                &file.base_dir,
+                &file.base_dir,
+                DirPerms::all(),
+                FilePerms::all(),
+            )
+            .with_context(|| format!("failed to open session directory {}", file.base_dir))
+            .map_err(Error::Runtime)?;
     }
+    Ok(builder.build())
 }
 
-/// The collected result of an agent invocation.
-#[derive(Debug, Clone)]
-pub struct AgentResult {
-    /// The full text produced by the agent.
-    pub text: String,
-    /// Why the agent stopped.
-    pub stop_reason: StopReason,
-    /// Optional token usage information.
-    pub usage: Option<Usage>,
-    /// Optional latency metrics.
-    pub metrics: Option<Metrics>,
+/// JIT mode: create everything fresh (dev mode, no caching). The store is
+/// returned even if instantiation fails, so its limiter can say why.
+async fn instantiate_jit(
+    host_state: HostState,
+    metered: bool,
+) -> wasmtime::Result<(Store<HostState>, wasmtime::Result<bindings::Agent>)> {
+    let engine = Engine::new(&wasm_config(metered))?;
+    deadline::start_epoch_ticker(&engine);
+    let wasm_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../strands-wasm/dist/strands-agent.wasm");
+    let component = Component::from_file(&engine, &wasm_path)
+        .with_context(|| format!("JIT: failed to load {}", wasm_path.display()))?;
+    let linker = new_linker(&engine)?;
+    let mut store = new_store(&engine, host_state)?;
+    let instance = bindings::Agent::instantiate_async(&mut store, &component, &linker).await;
+    Ok((store, instance))
+}
+
+/// A store with deadline checks and resource limits installed.
+fn new_store(engine: &Engine, host_state: HostState) -> wasmtime::Result<Store<HostState>> {
+    let mut store = Store::new(engine, host_state);
+    deadline::configure_store(&mut store);
+    limits::configure_store(&mut store)?;
+    Ok(store)
 }
 
 /// If the model config targets Bedrock and has no explicit credentials, try to
@@ -704,3 +1789,160 @@ fn parse_aws_profile(contents: &str, profile: &str) -> Option<(String, String, O
 
     Some((key_id?, secret?, token))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use bindings::strands::agent::tool_provider::Host as _;
+    use bindings::strands::agent::types::{CallToolArgs, CallToolsArgs};
+
+    use super::*;
+
+    /// Calls in flight, and the most seen at once.
+    #[derive(Default)]
+    struct Gauge {
+        active: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    /// A handler that holds a slot in `gauge` for a while and reports how
+    /// many calls were in flight alongside it.
+    fn counting_handler(gauge: &Arc<Gauge>) -> ToolHandlerFn {
+        let gauge = Arc::clone(gauge);
+        Arc::new(move |_input: &str, _context: &ToolContext| -> ToolFuture {
+            let gauge = Arc::clone(&gauge);
+            Box::pin(async move {
+                let active = gauge.active.fetch_add(1, Ordering::SeqCst) + 1;
+                gauge.peak.fetch_max(active, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let alongside = gauge.active.load(Ordering::SeqCst);
+                gauge.active.fetch_sub(1, Ordering::SeqCst);
+                Ok(alongside.max(active).to_string())
+            })
+        })
+    }
+
+    fn batch(names: &[&str]) -> CallToolsArgs {
+        CallToolsArgs {
+            calls: names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| CallToolArgs {
+                    name: (*name).to_string(),
+                    input: "{}".into(),
+                    tool_use_id: format!("call-{i}"),
+                    agent_state: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn call_tools_respects_the_parallelism_cap() {
+        let gauge = Arc::new(Gauge::default());
+        let mut state = HostState {
+            tool_handlers: Arc::new(HashMap::from([(
+                "work".to_string(),
+                counting_handler(&gauge),
+            )])),
+            max_parallel_tools: Some(2),
+            ..HostState::new()
+        };
+
+        let results = state.call_tools(batch(&["work"; 6])).await;
+
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(gauge.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn sequential_tools_run_alone() {
+        let gauge = Arc::new(Gauge::default());
+        let handler = counting_handler(&gauge);
+        let mut state = HostState {
+            tool_handlers: Arc::new(HashMap::from([
+                ("work".to_string(), Arc::clone(&handler)),
+                ("write".to_string(), handler),
+            ])),
+            sequential_tools: HashSet::from(["write".to_string()]),
+            ..HostState::new()
+        };
+
+        let results = state
+            .call_tools(batch(&["work", "work", "write", "work", "work"]))
+            .await;
+
+        let alongside: Vec<usize> = results
+            .into_iter()
+            .map(|result| result.unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(alongside[2], 1, "sequential call overlapped: {alongside:?}");
+        assert_eq!(gauge.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn tools_see_the_agent_state_sent_with_the_call() {
+        let whoami: ToolHandlerFn = Arc::new(|_input: &str, context: &ToolContext| -> ToolFuture {
+            let user = context.state::<String>("user").unwrap_or_default();
+            Box::pin(async move { Ok(user) })
+        });
+        let mut state = HostState {
+            tool_handlers: Arc::new(HashMap::from([("whoami".to_string(), whoami)])),
+            ..HostState::new()
+        };
+        let call = |agent_state: Option<&str>| CallToolArgs {
+            name: "whoami".into(),
+            input: "{}".into(),
+            tool_use_id: "call-0".into(),
+            agent_state: agent_state.map(str::to_string),
+        };
+
+        let sent = state.call_tool(call(Some(r#"{"user": "ada"}"#))).await;
+        assert_eq!(sent, Ok("ada".into()));
+        let mut batch = batch(&["whoami"]);
+        batch.calls[0].agent_state = Some(r#"{"user": "grace"}"#.into());
+        assert_eq!(state.call_tools(batch).await, [Ok("grace".into())]);
+        assert_eq!(state.call_tool(call(None)).await, Ok(String::new()));
+        assert_eq!(
+            state.call_tool(call(Some("not json"))).await,
+            Ok(String::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn runtime_tools_are_validated_until_removed() {
+        let echo: ToolHandlerFn = Arc::new(|input: &str, _context: &ToolContext| -> ToolFuture {
+            let input = input.to_string();
+            Box::pin(async move { Ok(input) })
+        });
+        let spec = ToolSpec {
+            name: "square".into(),
+            description: String::new(),
+            input_schema: r#"{"type": "object", "required": ["n"]}"#.into(),
+        };
+        let mut state = HostState::new();
+        let call = |input: &str| CallToolArgs {
+            name: "square".into(),
+            input: input.into(),
+            tool_use_id: "call-0".into(),
+            agent_state: None,
+        };
+
+        state.register_tool(&spec, echo);
+        assert_eq!(
+            state.call_tool(call(r#"{"n": 3}"#)).await,
+            Ok(r#"{"n": 3}"#.into())
+        );
+        let rejected = state.call_tool(call("{}")).await.unwrap_err();
+        assert!(rejected.contains("invalid input for tool 'square'"));
+
+        state.unregister_tool("square");
+        assert!(state.tool_inputs.validate("square", "{}").is_ok());
+        assert_eq!(
+            state.call_tool(call("{}")).await,
+            Err("no handler for tool 'square'".into())
+        );
+    }
+}
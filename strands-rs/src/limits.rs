@@ -0,0 +1,394 @@
+//! Per-agent resource limits.
+//!
+//! Memory, instance and table caps are enforced by [`Limiter`], installed as
+//! the store's `ResourceLimiter`, which records the limit it refused. Fuel
+//! metering makes the guest noticeably slower, so agents only run on a
+//! metered engine, with a separate precompiled component built by the `fuel`
+//! feature, when [`StoreLimits::fuel_per_invocation`] is set. Hitting a limit
+//! traps the guest, and like a timeout the agent is rebuilt from its history
+//! checkpoint before the next call.
+
+use std::fmt;
+
+use wasmtime::{ResourceLimiter, Store, Trap};
+
+use crate::HostState;
+
+/// Hard caps for a single agent's store. `None` means no cap.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::{Agent, StoreLimits};
+///
+/// let agent = Agent::builder()
+///     .limits(StoreLimits {
+///         max_memory: Some(512 << 20),
+///         fuel_per_invocation: Some(50_000_000_000),
+///         ..Default::default()
+///     })
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StoreLimits {
+    /// Total bytes of linear memory across all of the agent's memories.
+    pub max_memory: Option<usize>,
+    /// Core instances the component may create.
+    pub max_instances: Option<usize>,
+    /// Tables the component may create.
+    pub max_tables: Option<usize>,
+    /// Fuel available to each invocation. Roughly one unit per wasm instruction.
+    /// Setting it turns on fuel metering, which slows the guest down, and
+    /// needs the `fuel` feature unless the agent uses the JIT.
+    pub fuel_per_invocation: Option<u64>,
+}
+
+/// Which limit was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    Memory,
+    Instances,
+    Tables,
+    Fuel,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Memory => "memory",
+            Self::Instances => "instances",
+            Self::Tables => "tables",
+            Self::Fuel => "fuel",
+        })
+    }
+}
+
+/// Current resource consumption, from [`Agent::resource_usage`](crate::Agent::resource_usage).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Bytes of linear memory currently allocated.
+    pub memory: usize,
+    /// Fuel consumed since the current or last invocation started. Always
+    /// zero for agents that run without fuel metering.
+    pub fuel_consumed: u64,
+    /// Fuel left for the current invocation, if it has a budget.
+    pub fuel_remaining: Option<u64>,
+}
+
+/// The error that traps the guest when [`Limiter`] refuses an allocation.
+#[derive(Debug, thiserror::Error)]
+#[error("{0} limit exceeded")]
+pub(crate) struct LimitExceeded(Limit);
+
+/// Classify an error as a limit violation.
+pub(crate) fn exceeded(e: &wasmtime::Error) -> Option<Limit> {
+    if let Some(LimitExceeded(limit)) = e.downcast_ref() {
+        return Some(*limit);
+    }
+    (e.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel)).then_some(Limit::Fuel)
+}
+
+/// Enforces [`StoreLimits`], tracks memory use and records which limit the
+/// guest ran into.
+#[derive(Default)]
+pub(crate) struct Limiter {
+    pub(crate) limits: StoreLimits,
+    memory: usize,
+    tables: usize,
+    fuel_budget: u64,
+    breach: Option<Limit>,
+}
+
+impl Limiter {
+    pub(crate) fn new(limits: StoreLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// The limit refused since the invocation started, if any.
+    pub(crate) fn breach(&self) -> Option<Limit> {
+        self.breach
+    }
+
+    /// Classify a failed instantiation. Wasmtime refuses instances past
+    /// [`StoreLimits::max_instances`] itself, without asking the limiter, so
+    /// under an instance cap any failure other than a trap or a refusal
+    /// recorded here is put down to that cap.
+    pub(crate) fn instantiation_failed(&mut self, e: &wasmtime::Error) -> Option<Limit> {
+        if self.breach.is_none()
+            && self.limits.max_instances.is_some()
+            && e.downcast_ref::<Trap>().is_none()
+        {
+            self.breach = Some(Limit::Instances);
+        }
+        self.breach
+    }
+
+    fn refuse(&mut self, limit: Limit) -> wasmtime::Error {
+        self.breach = Some(limit);
+        LimitExceeded(limit).into()
+    }
+}
+
+impl ResourceLimiter for Limiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let total = self.memory - current + desired;
+        if let Some(max) = self.limits.max_memory
+            && total > max
+        {
+            return Err(self.refuse(Limit::Memory));
+        }
+        self.memory = total;
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        // Wasmtime announces every new table as growing from zero.
+        if current == 0 {
+            if let Some(max) = self.limits.max_tables
+                && self.tables >= max
+            {
+                return Err(self.refuse(Limit::Tables));
+            }
+            self.tables += 1;
+        }
+        Ok(true)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.max_instances.unwrap_or(10_000)
+    }
+
+    /// Tables are counted in [`table_growing`](Self::table_growing) instead,
+    /// so that refusals are recorded.
+    fn tables(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// Install the limiter and an initial fuel budget on a new store.
+pub(crate) fn configure_store(store: &mut Store<HostState>) -> wasmtime::Result<()> {
+    store.limiter(|state| &mut state.limiter);
+    reset_fuel(store)
+}
+
+/// Refill the store's fuel for a new invocation and forget any earlier
+/// breach. Fails if the agent has a fuel budget but its engine does not meter
+/// fuel.
+pub(crate) fn reset_fuel(store: &mut Store<HostState>) -> wasmtime::Result<()> {
+    store.data_mut().limiter.breach = None;
+    let budget = store.data().limiter.limits.fuel_per_invocation;
+    let metered = store.get_fuel().is_ok();
+    if !metered {
+        if budget.is_some() {
+            anyhow::bail!("fuel_per_invocation is set but the engine does not meter fuel");
+        }
+        return Ok(());
+    }
+    let budget = budget.unwrap_or(u64::MAX);
+    store.data_mut().limiter.fuel_budget = budget;
+    store.set_fuel(budget)
+}
+
+/// Report the store's current consumption.
+pub(crate) fn usage(store: &Store<HostState>) -> ResourceUsage {
+    let limiter = &store.data().limiter;
+    let remaining = store.get_fuel().ok();
+    ResourceUsage {
+        memory: limiter.memory,
+        fuel_consumed: remaining.map_or(0, |remaining| limiter.fuel_budget - remaining),
+        fuel_remaining: limiter.limits.fuel_per_invocation.and(remaining),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmtime::{Engine, Instance, Module};
+
+    use super::*;
+
+    fn store(metered: bool, limits: StoreLimits) -> Store<HostState> {
+        let engine = Engine::new(&crate::wasm_config(metered)).unwrap();
+        let state = HostState {
+            limiter: Limiter::new(limits),
+            ..HostState::new()
+        };
+        crate::new_store(&engine, state).unwrap()
+    }
+
+    async fn instantiate(store: &mut Store<HostState>, wat: &str) -> wasmtime::Result<Instance> {
+        let module = Module::new(store.engine(), wat)?;
+        Instance::new_async(&mut *store, &module, &[]).await
+    }
+
+    const PAGE: usize = 64 << 10;
+
+    #[test]
+    fn memory_is_counted_and_refused_past_the_cap() {
+        let mut limiter = Limiter::new(StoreLimits {
+            max_memory: Some(3 * PAGE),
+            ..Default::default()
+        });
+        assert!(limiter.memory_growing(0, PAGE, None).unwrap());
+        assert!(limiter.memory_growing(0, PAGE, None).unwrap());
+        assert!(limiter.memory_growing(PAGE, 2 * PAGE, None).unwrap());
+        assert_eq!(limiter.memory, 3 * PAGE);
+        assert_eq!(limiter.breach(), None);
+
+        let e = limiter.memory_growing(PAGE, 2 * PAGE, None).unwrap_err();
+        assert_eq!(exceeded(&e), Some(Limit::Memory));
+        assert_eq!(limiter.breach(), Some(Limit::Memory));
+        assert_eq!(limiter.memory, 3 * PAGE);
+    }
+
+    #[test]
+    fn new_tables_are_refused_past_the_cap() {
+        let mut limiter = Limiter::new(StoreLimits {
+            max_tables: Some(1),
+            ..Default::default()
+        });
+        assert!(limiter.table_growing(0, 10, None).unwrap());
+        // Growing an existing table is not a new one.
+        assert!(limiter.table_growing(10, 20, None).unwrap());
+
+        let e = limiter.table_growing(0, 10, None).unwrap_err();
+        assert_eq!(exceeded(&e), Some(Limit::Tables));
+        assert_eq!(limiter.breach(), Some(Limit::Tables));
+    }
+
+    #[tokio::test]
+    async fn guest_traps_when_memory_growth_is_refused() {
+        let mut store = store(
+            false,
+            StoreLimits {
+                max_memory: Some(2 * PAGE),
+                ..Default::default()
+            },
+        );
+        let instance = instantiate(
+            &mut store,
+            r#"(module
+                (memory 1)
+                (func (export "grow") (param i32) (result i32)
+                    (memory.grow (local.get 0))))"#,
+        )
+        .await
+        .unwrap();
+        let grow = instance
+            .get_typed_func::<i32, i32>(&mut store, "grow")
+            .unwrap();
+
+        assert_eq!(grow.call_async(&mut store, 1).await.unwrap(), 1);
+        assert_eq!(usage(&store).memory, 2 * PAGE);
+        let e = grow.call_async(&mut store, 1).await.unwrap_err();
+        assert_eq!(exceeded(&e), Some(Limit::Memory));
+        assert_eq!(store.data().limiter.breach(), Some(Limit::Memory));
+        assert_eq!(usage(&store).memory, 2 * PAGE);
+    }
+
+    #[tokio::test]
+    async fn instances_past_the_cap_are_recorded() {
+        let mut store = store(
+            false,
+            StoreLimits {
+                max_instances: Some(1),
+                ..Default::default()
+            },
+        );
+        instantiate(&mut store, "(module)").await.unwrap();
+        let e = instantiate(&mut store, "(module)").await.unwrap_err();
+        assert_eq!(exceeded(&e), None);
+        assert_eq!(
+            store.data_mut().limiter.instantiation_failed(&e),
+            Some(Limit::Instances)
+        );
+    }
+
+    #[tokio::test]
+    async fn uncapped_instantiation_failures_are_not_limits() {
+        let mut store = store(false, StoreLimits::default());
+        let e = instantiate(&mut store, r#"(module (import "host" "f" (func)))"#)
+            .await
+            .unwrap_err();
+        assert_eq!(store.data_mut().limiter.instantiation_failed(&e), None);
+    }
+
+    #[tokio::test]
+    async fn fuel_and_breaches_reset_with_each_invocation() {
+        let budget = 100_000;
+        let mut store = store(
+            true,
+            StoreLimits {
+                fuel_per_invocation: Some(budget),
+                ..Default::default()
+            },
+        );
+        let instance = instantiate(
+            &mut store,
+            r#"(module
+                (func (export "spin") (param i32)
+                    (loop
+                        (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+                        (br_if 0 (local.get 0)))))"#,
+        )
+        .await
+        .unwrap();
+        let spin = instance
+            .get_typed_func::<i32, ()>(&mut store, "spin")
+            .unwrap();
+
+        spin.call_async(&mut store, 100).await.unwrap();
+        let used = usage(&store);
+        assert!(used.fuel_consumed > 0);
+        assert_eq!(used.fuel_remaining, Some(budget - used.fuel_consumed));
+
+        let e = spin.call_async(&mut store, i32::MAX).await.unwrap_err();
+        assert_eq!(exceeded(&e), Some(Limit::Fuel));
+        assert_eq!(usage(&store).fuel_remaining, Some(0));
+
+        store.data_mut().limiter.breach = Some(Limit::Memory);
+        reset_fuel(&mut store).unwrap();
+        let used = usage(&store);
+        assert_eq!(used.fuel_consumed, 0);
+        assert_eq!(used.fuel_remaining, Some(budget));
+        assert_eq!(store.data().limiter.breach(), None);
+    }
+
+    #[test]
+    fn unmetered_usage_reports_no_fuel() {
+        let mut store = store(false, StoreLimits::default());
+        reset_fuel(&mut store).unwrap();
+        let used = usage(&store);
+        assert_eq!(used.fuel_consumed, 0);
+        assert_eq!(used.fuel_remaining, None);
+    }
+
+    #[test]
+    fn a_fuel_budget_needs_a_metered_engine() {
+        let engine = Engine::new(&crate::wasm_config(false)).unwrap();
+        let state = HostState {
+            limiter: Limiter::new(StoreLimits {
+                fuel_per_invocation: Some(1),
+                ..Default::default()
+            }),
+            ..HostState::new()
+        };
+        assert!(crate::new_store(&engine, state).is_err());
+    }
+}
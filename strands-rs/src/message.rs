@@ -0,0 +1,381 @@
+//! Conversation history types.
+//!
+//! These mirror the TS SDK's serialized message format (`MessageData`), which
+//! is what the guest exchanges through `get-messages` / `set-messages`. Each
+//! content block is an object keyed by its kind, e.g. `{"text": "..."}` or
+//! `{"toolUse": {...}}`. Binary payloads are base64-encoded strings.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ToolFailure, ToolResultEvent};
+
+/// A message in the conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: Vec<ContentBlock>,
+}
+
+impl Message {
+    /// A user message with a single text block.
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: vec![ContentBlock::Text(text.into())],
+        }
+    }
+
+    /// An assistant message with a single text block.
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: vec![ContentBlock::Text(text.into())],
+        }
+    }
+
+    /// The message's text blocks, concatenated.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Who sent a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// A block of content within a message.
+///
+/// Block kinds without a dedicated variant (video, guard content, citations)
+/// are kept as [`ContentBlock::Other`] so history round-trips unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ContentBlock {
+    Text(String),
+    ToolUse(ToolUse),
+    ToolResult(ToolResult),
+    Image(Image),
+    Document(Document),
+    Reasoning(Reasoning),
+    CachePoint(CachePoint),
+    #[serde(untagged)]
+    Other(Value),
+}
+
+/// A request from the model to run a tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolUse {
+    pub name: String,
+    pub tool_use_id: String,
+    pub input: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_signature: Option<String>,
+}
+
+/// The outcome of a tool call, sent back to the model.
+///
+/// Tool handlers can build one and return it as their output; the guest fills
+/// in `tool_use_id`.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::{Agent, ToolResult};
+///
+/// let agent = Agent::builder()
+///     .tool(
+///         "render_chart",
+///         "Render the sales chart.",
+///         serde_json::json!({ "type": "object" }),
+///         |_input: &str| {
+///             let png = std::fs::read("chart.png").map_err(|e| e.to_string())?;
+///             Ok(ToolResult::success()
+///                 .text("Sales by quarter:")
+///                 .image("image/png", &png)
+///                 .into())
+///         },
+///     )
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolResult {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tool_use_id: String,
+    pub status: ToolResultStatus,
+    pub content: Vec<ToolResultContent>,
+}
+
+impl ToolResult {
+    /// A successful result with no content yet.
+    pub fn success() -> Self {
+        Self {
+            tool_use_id: String::new(),
+            status: ToolResultStatus::Success,
+            content: Vec::new(),
+        }
+    }
+
+    /// A failed result explaining what went wrong.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            status: ToolResultStatus::Error,
+            ..Self::success()
+        }
+        .text(message)
+    }
+
+    /// Append a text part.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.content.push(ToolResultContent::Text(text.into()));
+        self
+    }
+
+    /// Append a JSON part.
+    pub fn json(mut self, value: impl Into<Value>) -> Self {
+        self.content.push(ToolResultContent::Json(value.into()));
+        self
+    }
+
+    /// Append an image. `media_type` is a MIME type such as `image/png`, or a
+    /// bare format such as `png`.
+    pub fn image(mut self, media_type: &str, bytes: &[u8]) -> Self {
+        self.content.push(ToolResultContent::Image(Image {
+            format: image_format(media_type).into(),
+            source: ImageSource::Bytes(BASE64.encode(bytes)),
+        }));
+        self
+    }
+
+    /// Append a document. `media_type` is a MIME type such as
+    /// `application/pdf`, or a bare format such as `pdf`. Fails for types
+    /// with no document format.
+    pub fn document(
+        mut self,
+        name: impl Into<String>,
+        media_type: &str,
+        bytes: &[u8],
+    ) -> crate::Result<Self> {
+        let format = document_format(media_type).ok_or_else(|| {
+            crate::Error::Runtime(anyhow::anyhow!("unsupported document type '{media_type}'"))
+        })?;
+        self.content.push(ToolResultContent::Document(Document {
+            name: name.into(),
+            format: format.into(),
+            source: DocumentSource::Bytes(BASE64.encode(bytes)),
+            citations: None,
+            context: None,
+        }));
+        Ok(self)
+    }
+
+    /// The result a [`ToolResultEvent`] reported, including its image and
+    /// document parts.
+    pub fn from_event(event: &ToolResultEvent) -> serde_json::Result<Self> {
+        Ok(Self {
+            tool_use_id: event.tool_use_id.clone(),
+            status: serde_json::from_value(Value::String(event.status.clone()))?,
+            content: serde_json::from_str(&event.content)?,
+        })
+    }
+
+    /// The result as tool handler output.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("tool results always serialize")
+    }
+
+    /// An error result for a call the tool guard failed.
+    pub(crate) fn failed(failure: ToolFailure, message: String) -> Self {
+        Self::error(message).json(serde_json::json!({ FAILURE: failure }))
+    }
+
+    /// Why the call failed, if it timed out or its circuit was open rather
+    /// than the tool reporting an error.
+    pub fn failure(&self) -> Option<ToolFailure> {
+        self.content.iter().find_map(|part| match part {
+            ToolResultContent::Json(value) => {
+                serde_json::from_value(value.get(FAILURE)?.clone()).ok()
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Key of the JSON part marking a result the tool guard produced.
+const FAILURE: &str = "toolFailure";
+
+impl From<ToolResult> for String {
+    fn from(result: ToolResult) -> Self {
+        result.to_json()
+    }
+}
+
+/// The image format for a MIME type, e.g. `jpeg` for `image/jpeg`.
+fn image_format(media_type: &str) -> &str {
+    media_type.strip_prefix("image/").unwrap_or(media_type)
+}
+
+/// Document formats models accept.
+const DOCUMENT_FORMATS: [&str; 11] = [
+    "pdf", "csv", "doc", "docx", "xls", "xlsx", "html", "txt", "md", "json", "xml",
+];
+
+/// The document format for a MIME type, e.g. `docx` for Word documents, or
+/// `None` if there is none.
+fn document_format(media_type: &str) -> Option<&'static str> {
+    let format = match media_type {
+        "application/pdf" => "pdf",
+        "text/csv" => "csv",
+        "application/msword" => "doc",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/markdown" => "md",
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        bare => return DOCUMENT_FORMATS.into_iter().find(|&format| format == bare),
+    };
+    Some(format)
+}
+
+/// Whether a tool call succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolResultStatus {
+    Success,
+    Error,
+}
+
+/// A piece of tool output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolResultContent {
+    Text(String),
+    Json(Value),
+    Image(Image),
+    Document(Document),
+}
+
+/// An image, e.g. `format: "png"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Image {
+    pub format: String,
+    pub source: ImageSource,
+}
+
+/// Where an image's data lives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageSource {
+    /// Base64-encoded image data.
+    Bytes(String),
+    S3Location(S3Location),
+    Url(String),
+}
+
+/// A document, e.g. `format: "pdf"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub name: String,
+    pub format: String,
+    pub source: DocumentSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub citations: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+/// Where a document's data lives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocumentSource {
+    /// Base64-encoded document data.
+    Bytes(String),
+    Text(String),
+    /// Structured content; only text blocks are allowed.
+    Content(Vec<ContentBlock>),
+    S3Location(S3Location),
+}
+
+/// An object in S3.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Location {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket_owner: Option<String>,
+}
+
+/// Model reasoning. `redacted_content` is base64-encoded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reasoning {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted_content: Option<String>,
+}
+
+/// Marks a prompt-caching boundary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePoint {
+    pub cache_type: String,
+}
+
+impl Default for CachePoint {
+    fn default() -> Self {
+        Self {
+            cache_type: "default".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_formats() {
+        assert_eq!(document_format("application/pdf"), Some("pdf"));
+        assert_eq!(document_format("text/xml"), Some("xml"));
+        assert_eq!(document_format("docx"), Some("docx"));
+        assert_eq!(document_format("application/zip"), None);
+        assert_eq!(document_format("zip"), None);
+        assert!(
+            ToolResult::success()
+                .document("a", "image/png", b"")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn marks_guard_failures() {
+        let timeout = ToolResult::failed(ToolFailure::Timeout, "timed out".into());
+        assert_eq!(timeout.status, ToolResultStatus::Error);
+        assert_eq!(timeout.failure(), Some(ToolFailure::Timeout));
+        assert_eq!(ToolResult::error("boom").failure(), None);
+        assert_eq!(ToolResult::success().json(1).failure(), None);
+    }
+}
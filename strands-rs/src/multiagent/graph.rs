@@ -0,0 +1,554 @@
+//! Graph orchestration.
+//!
+//! Nodes run in waves: every node whose incoming edges have all been decided
+//! and at least one of which was traversed runs concurrently with the rest of
+//! its wave. Edges leave only completed nodes, and a node none of whose edges
+//! were traversed is skipped, which also decides its outgoing edges. Entry
+//! points run first with the task as input; other nodes receive the task and
+//! the output of each node that traversed an edge to them.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use futures_util::future::join_all;
+
+use super::{EventSender, MultiAgentEvent, MultiAgentStream, NodeResult, Status, run_node};
+use crate::{Agent, Error, Result, Usage, result};
+
+/// An event from a running [`Graph`].
+pub type GraphEvent = MultiAgentEvent<GraphResult>;
+
+/// The event stream of a running [`Graph`].
+pub type GraphStream<'a> = MultiAgentStream<'a, GraphResult>;
+
+type Condition = Box<dyn Fn(&GraphState) -> bool + Send + Sync>;
+
+struct Node {
+    id: String,
+    agent: Agent,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    condition: Option<Condition>,
+}
+
+/// Results so far, passed to edge conditions.
+#[derive(Debug, Default)]
+pub struct GraphState {
+    pub results: HashMap<String, NodeResult>,
+    /// Node IDs in the order they finished.
+    pub execution_order: Vec<String>,
+}
+
+impl GraphState {
+    /// The result of a node that has run.
+    pub fn result(&self, node_id: &str) -> Option<&NodeResult> {
+        self.results.get(node_id)
+    }
+}
+
+/// The result of a [`Graph`] run.
+#[derive(Debug)]
+pub struct GraphResult {
+    /// `Failed` if any node failed, else `Interrupted` if any node was
+    /// interrupted, else `Completed`.
+    pub status: Status,
+    pub results: HashMap<String, NodeResult>,
+    /// Node IDs in the order they finished.
+    pub execution_order: Vec<String>,
+    /// Nodes that never ran because no edge to them was traversed.
+    pub skipped: Vec<String>,
+    /// Usage summed across every node.
+    pub total_usage: Usage,
+    pub execution_time: Duration,
+}
+
+/// Builder for [`Graph`].
+#[derive(Default)]
+pub struct GraphBuilder {
+    nodes: Vec<Node>,
+    edges: Vec<(String, String, Option<Condition>)>,
+    entry_points: Vec<String>,
+}
+
+impl GraphBuilder {
+    /// Add an agent as a node.
+    pub fn add_node(mut self, id: impl Into<String>, agent: Agent) -> Self {
+        self.nodes.push(Node {
+            id: id.into(),
+            agent,
+        });
+        self
+    }
+
+    /// Add an edge that is traversed whenever `from` completes.
+    pub fn add_edge(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges.push((from.into(), to.into(), None));
+        self
+    }
+
+    /// Add an edge that is traversed when `from` completes and `condition`
+    /// holds.
+    pub fn add_conditional_edge(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        condition: impl Fn(&GraphState) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.edges
+            .push((from.into(), to.into(), Some(Box::new(condition))));
+        self
+    }
+
+    /// Run this node first. Defaults to every node without incoming edges.
+    pub fn entry_point(mut self, id: impl Into<String>) -> Self {
+        self.entry_points.push(id.into());
+        self
+    }
+
+    /// Validate the graph. Fails on duplicate or unknown node IDs and cycles.
+    pub fn build(self) -> Result<Graph> {
+        let invalid = |reason: String| Error::MultiAgent(reason);
+        if self.nodes.is_empty() {
+            return Err(invalid("graph has no nodes".into()));
+        }
+
+        let mut index = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if index.insert(node.id.clone(), i).is_some() {
+                return Err(invalid(format!("duplicate node '{}'", node.id)));
+            }
+        }
+        let lookup = |id: &str| {
+            index
+                .get(id)
+                .copied()
+                .ok_or_else(|| invalid(format!("unknown node '{id}'")))
+        };
+
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for (from, to, condition) in self.edges {
+            edges.push(Edge {
+                from: lookup(&from)?,
+                to: lookup(&to)?,
+                condition,
+            });
+        }
+
+        let mut entry_points = self
+            .entry_points
+            .iter()
+            .map(|id| lookup(id))
+            .collect::<Result<Vec<_>>>()?;
+        if entry_points.is_empty() {
+            entry_points = (0..self.nodes.len())
+                .filter(|&i| !edges.iter().any(|edge| edge.to == i))
+                .collect();
+        }
+        if entry_points.is_empty() {
+            return Err(invalid("graph has no entry points".into()));
+        }
+
+        let rank = topological_rank(self.nodes.len(), &edges)
+            .map_err(|i| invalid(format!("graph has a cycle through '{}'", self.nodes[i].id)))?;
+
+        Ok(Graph {
+            nodes: self.nodes,
+            edges,
+            entry_points,
+            rank,
+        })
+    }
+}
+
+/// A directed acyclic graph of agents.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::Agent;
+/// use strands::multiagent::Graph;
+///
+/// let researcher = Agent::builder().system_prompt("Research the topic.").build().await?;
+/// let writer = Agent::builder().system_prompt("Write a short report.").build().await?;
+/// let reviewer = Agent::builder().system_prompt("Review the report.").build().await?;
+///
+/// let mut graph = Graph::builder()
+///     .add_node("research", researcher)
+///     .add_node("write", writer)
+///     .add_node("review", reviewer)
+///     .add_edge("research", "write")
+///     .add_conditional_edge("write", "review", |state| {
+///         state.result("write").and_then(|r| r.text()).is_some_and(|t| t.len() > 200)
+///     })
+///     .build()?;
+///
+/// let result = graph.invoke("The history of the printing press").await;
+/// for id in &result.execution_order {
+///     println!("{id}: {:?}", result.results[id].text());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    entry_points: Vec<usize>,
+    /// Position of each node in a topological order.
+    rank: Vec<usize>,
+}
+
+impl Graph {
+    /// Create a builder for a graph.
+    pub fn builder() -> GraphBuilder {
+        GraphBuilder::default()
+    }
+
+    /// The agent at a node.
+    pub fn agent(&self, node_id: &str) -> Option<&Agent> {
+        self.nodes
+            .iter()
+            .find(|node| node.id == node_id)
+            .map(|node| &node.agent)
+    }
+
+    /// The agent at a node, mutably.
+    pub fn agent_mut(&mut self, node_id: &str) -> Option<&mut Agent> {
+        self.nodes
+            .iter_mut()
+            .find(|node| node.id == node_id)
+            .map(|node| &mut node.agent)
+    }
+
+    /// Run the graph and stream node events, ending with
+    /// [`MultiAgentEvent::Done`].
+    pub fn stream<'a>(&'a mut self, task: &str) -> GraphStream<'a> {
+        let task = task.to_string();
+        MultiAgentStream::new(move |events| self.run(task, events))
+    }
+
+    /// Run the graph to completion.
+    pub async fn invoke(&mut self, task: &str) -> GraphResult {
+        let mut stream = self.stream(task);
+        while let Some(event) = stream.next().await {
+            if let MultiAgentEvent::Done(result) = event {
+                return *result;
+            }
+        }
+        unreachable!("graph stream ended without a result")
+    }
+
+    async fn run(&mut self, task: String, events: EventSender<GraphResult>) -> GraphResult {
+        let start = Instant::now();
+        let count = self.nodes.len();
+        let mut state = GraphState::default();
+        let mut decided = vec![false; count];
+        // Sources of the traversed edges into each node.
+        let mut inputs: Vec<Vec<usize>> = vec![Vec::new(); count];
+        let mut skipped = Vec::new();
+
+        let mut wave = self.entry_points.clone();
+        while !wave.is_empty() {
+            wave.sort_by_key(|&i| self.rank[i]);
+            for &i in &wave {
+                decided[i] = true;
+            }
+
+            let mut prompts: HashMap<usize, String> = wave
+                .iter()
+                .map(|&i| (i, self.node_input(&task, &inputs[i], &state)))
+                .collect();
+            let running = self
+                .nodes
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(i, node)| Some((i, node, prompts.remove(&i)?)))
+                .map(|(i, node, prompt)| {
+                    let events = events.clone();
+                    async move {
+                        let result = run_node(&node.id, &mut node.agent, &prompt, None, &events);
+                        (i, result.await)
+                    }
+                });
+            let mut finished = join_all(running).await;
+            finished.sort_by_key(|&(i, _)| self.rank[i]);
+
+            for (i, node_result) in finished {
+                let id = self.nodes[i].id.clone();
+                state.execution_order.push(id.clone());
+                state.results.insert(id, node_result);
+            }
+
+            for &from in &wave {
+                let from_id = &self.nodes[from].id;
+                if state.results[from_id].status != Status::Completed {
+                    continue;
+                }
+                let to_node_ids: Vec<String> = traverse(&self.edges, from, &state, &mut inputs)
+                    .into_iter()
+                    .map(|to| self.nodes[to].id.clone())
+                    .collect();
+                if !to_node_ids.is_empty() {
+                    let _ = events.send(MultiAgentEvent::Handoff {
+                        from_node_ids: vec![from_id.clone()],
+                        to_node_ids,
+                    });
+                }
+            }
+
+            wave = next_wave(&self.edges, &mut decided, &inputs, &mut skipped);
+        }
+
+        // Nodes unreachable from the entry points never became ready.
+        skipped.extend((0..count).filter(|&i| !decided[i]));
+        let skipped = skipped
+            .into_iter()
+            .map(|i| self.nodes[i].id.clone())
+            .collect();
+
+        let mut total_usage = result::empty_usage();
+        let mut status = Status::Completed;
+        for node_result in state.results.values() {
+            if let Some(agent_result) = &node_result.result {
+                result::accumulate(&mut total_usage, &agent_result.total_usage);
+            }
+            status = match (status, node_result.status) {
+                (Status::Failed, _) | (_, Status::Failed) => Status::Failed,
+                (Status::Interrupted, _) | (_, Status::Interrupted) => Status::Interrupted,
+                _ => Status::Completed,
+            };
+        }
+
+        GraphResult {
+            status,
+            results: state.results,
+            execution_order: state.execution_order,
+            skipped,
+            total_usage,
+            execution_time: start.elapsed(),
+        }
+    }
+
+    /// The task alone for entry points, else the task and the output of every
+    /// node that handed off to this one.
+    fn node_input(&self, task: &str, sources: &[usize], state: &GraphState) -> String {
+        if sources.is_empty() {
+            return task.to_string();
+        }
+        let mut input = format!("Original task: {task}\n\nInputs from previous nodes:\n");
+        for &source in sources {
+            let id = &self.nodes[source].id;
+            let text = state.result(id).and_then(NodeResult::text).unwrap_or("");
+            input.push_str(&format!("\nFrom {id}:\n{text}\n"));
+        }
+        input
+    }
+}
+
+/// Each node's position in a topological order of the graph, by Kahn's
+/// algorithm. Fails with a node on a cycle if there is one.
+fn topological_rank(count: usize, edges: &[Edge]) -> std::result::Result<Vec<usize>, usize> {
+    let mut incoming = vec![0; count];
+    for edge in edges {
+        incoming[edge.to] += 1;
+    }
+    let mut order: Vec<usize> = (0..count).filter(|&i| incoming[i] == 0).collect();
+    let mut next = 0;
+    while let Some(&node) = order.get(next) {
+        next += 1;
+        for edge in edges.iter().filter(|edge| edge.from == node) {
+            incoming[edge.to] -= 1;
+            if incoming[edge.to] == 0 {
+                order.push(edge.to);
+            }
+        }
+    }
+    // Leftover nodes are on a cycle.
+    if let Some(i) = (0..count).find(|&i| incoming[i] > 0) {
+        return Err(i);
+    }
+
+    let mut rank = vec![0; count];
+    for (position, &node) in order.iter().enumerate() {
+        rank[node] = position;
+    }
+    Ok(rank)
+}
+
+/// Traverse the edges leaving a completed node whose conditions hold,
+/// recording it as an input of each target. Returns the targets.
+fn traverse(
+    edges: &[Edge],
+    from: usize,
+    state: &GraphState,
+    inputs: &mut [Vec<usize>],
+) -> Vec<usize> {
+    let mut targets = Vec::new();
+    for edge in edges.iter().filter(|edge| edge.from == from) {
+        if edge.condition.as_ref().is_none_or(|holds| holds(state)) {
+            inputs[edge.to].push(from);
+            targets.push(edge.to);
+        }
+    }
+    targets
+}
+
+/// The next wave: nodes whose sources are all decided. Those with no
+/// traversed edge are skipped instead, which can decide further nodes, so
+/// this repeats until nothing more is ready.
+fn next_wave(
+    edges: &[Edge],
+    decided: &mut [bool],
+    inputs: &[Vec<usize>],
+    skipped: &mut Vec<usize>,
+) -> Vec<usize> {
+    let mut wave = Vec::new();
+    loop {
+        let ready: Vec<usize> = (0..decided.len())
+            .filter(|&i| !decided[i] && !wave.contains(&i))
+            .filter(|&i| {
+                edges
+                    .iter()
+                    .filter(|edge| edge.to == i)
+                    .all(|edge| decided[edge.from])
+            })
+            .collect();
+        if ready.is_empty() {
+            return wave;
+        }
+        for i in ready {
+            if inputs[i].is_empty() {
+                decided[i] = true;
+                skipped.push(i);
+            } else {
+                wave.push(i);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: usize, to: usize) -> Edge {
+        Edge {
+            from,
+            to,
+            condition: None,
+        }
+    }
+
+    fn never(from: usize, to: usize) -> Edge {
+        Edge {
+            from,
+            to,
+            condition: Some(Box::new(|_| false)),
+        }
+    }
+
+    fn completed(state: &mut GraphState, id: &str) {
+        state.results.insert(
+            id.into(),
+            NodeResult {
+                status: Status::Completed,
+                result: None,
+                error: None,
+                execution_time: Duration::ZERO,
+            },
+        );
+    }
+
+    #[test]
+    fn ranks_nodes_in_topological_order() {
+        // 3 -> 1 -> 0, 3 -> 2 -> 0
+        let edges = [edge(3, 1), edge(3, 2), edge(1, 0), edge(2, 0)];
+        let rank = topological_rank(4, &edges).unwrap();
+        for edge in &edges {
+            assert!(rank[edge.from] < rank[edge.to]);
+        }
+    }
+
+    #[test]
+    fn reports_a_node_on_a_cycle() {
+        let edges = [edge(0, 1), edge(1, 2), edge(2, 1)];
+        let node = topological_rank(3, &edges).unwrap_err();
+        assert!(node == 1 || node == 2);
+    }
+
+    #[test]
+    fn waits_for_every_source_before_running() {
+        // 0 -> 1 -> 2 -> 3, and 0 -> 3
+        let edges = [edge(0, 1), edge(1, 2), edge(2, 3), edge(0, 3)];
+        let state = GraphState::default();
+        let mut decided = vec![true, false, false, false];
+        let mut inputs = vec![Vec::new(); 4];
+        let mut skipped = Vec::new();
+
+        assert_eq!(traverse(&edges, 0, &state, &mut inputs), [1, 3]);
+        assert_eq!(next_wave(&edges, &mut decided, &inputs, &mut skipped), [1]);
+
+        decided[1] = true;
+        traverse(&edges, 1, &state, &mut inputs);
+        assert_eq!(next_wave(&edges, &mut decided, &inputs, &mut skipped), [2]);
+
+        decided[2] = true;
+        traverse(&edges, 2, &state, &mut inputs);
+        assert_eq!(next_wave(&edges, &mut decided, &inputs, &mut skipped), [3]);
+        assert_eq!(inputs[3], [0, 2]);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn skips_nodes_no_traversed_edge_reaches() {
+        // 0 -x-> 1 -> 2, and 0 -> 3 -> 2
+        let edges = [never(0, 1), edge(1, 2), edge(0, 3), edge(3, 2)];
+        let mut state = GraphState::default();
+        completed(&mut state, "a");
+        let mut decided = vec![true, false, false, false];
+        let mut inputs = vec![Vec::new(); 4];
+        let mut skipped = Vec::new();
+
+        assert_eq!(traverse(&edges, 0, &state, &mut inputs), [3]);
+        assert_eq!(next_wave(&edges, &mut decided, &inputs, &mut skipped), [3]);
+        assert_eq!(skipped, [1]);
+
+        decided[3] = true;
+        traverse(&edges, 3, &state, &mut inputs);
+        assert_eq!(next_wave(&edges, &mut decided, &inputs, &mut skipped), [2]);
+        assert_eq!(inputs[2], [3]);
+    }
+
+    #[test]
+    fn skips_cascade_through_a_chain() {
+        // 0 -x-> 1 -> 2 -> 3
+        let edges = [never(0, 1), edge(1, 2), edge(2, 3)];
+        let state = GraphState::default();
+        let mut decided = vec![true, false, false, false];
+        let mut inputs = vec![Vec::new(); 4];
+        let mut skipped = Vec::new();
+
+        assert!(traverse(&edges, 0, &state, &mut inputs).is_empty());
+        assert!(next_wave(&edges, &mut decided, &inputs, &mut skipped).is_empty());
+        assert_eq!(skipped, [1, 2, 3]);
+        assert!(decided.iter().all(|&d| d));
+    }
+
+    #[test]
+    fn conditions_see_the_results_so_far() {
+        let edges = [Edge {
+            from: 0,
+            to: 1,
+            condition: Some(Box::new(|state: &GraphState| state.result("a").is_some())),
+        }];
+        let mut state = GraphState::default();
+        let mut inputs = vec![Vec::new(); 2];
+        assert!(traverse(&edges, 0, &state, &mut inputs).is_empty());
+
+        completed(&mut state, "a");
+        assert_eq!(traverse(&edges, 0, &state, &mut inputs), [1]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Multi-agent orchestration.
+//!
+//! [`Graph`] runs agents as nodes of a directed acyclic graph; [`Swarm`] lets
+//! agents hand control to each other until one finishes. Each pattern
+//! owns its agents, records a [`NodeResult`] per node execution and reports
+//! progress as a stream of [`MultiAgentEvent`]s ending in the pattern's result.
+//!
+//! Node events are pushed to a channel while the orchestration future runs, and
+//! the stream drains the channel between polls of that future. Nothing runs
+//! unless the stream is polled, and dropping it stops the run with every agent
+//! still owned by the pattern.
+
+mod graph;
+mod swarm;
+
+pub use graph::{Graph, GraphBuilder, GraphEvent, GraphResult, GraphState, GraphStream};
+pub use swarm::{
+    HANDOFF_TOOL, Handoff, Swarm, SwarmBuilder, SwarmEvent, SwarmResult, SwarmStream, SwarmTurn,
+};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::{Agent, AgentResult, Error, StreamEvent, ToolSpec};
+
+/// Outcome of a node, or of a whole orchestration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Status {
+    Completed,
+    Failed,
+    /// The agent stopped on unanswered interrupts.
+    Interrupted,
+}
+
+/// The result of running one node.
+#[derive(Debug)]
+pub struct NodeResult {
+    pub status: Status,
+    /// The agent's result. `None` if the node failed.
+    pub result: Option<AgentResult>,
+    /// Why the node failed.
+    pub error: Option<Error>,
+    pub execution_time: Duration,
+}
+
+impl NodeResult {
+    fn new(result: crate::Result<AgentResult>, execution_time: Duration) -> Self {
+        match result {
+            Ok(result) => Self {
+                status: if result.is_interrupted() {
+                    Status::Interrupted
+                } else {
+                    Status::Completed
+                },
+                result: Some(result),
+                error: None,
+                execution_time,
+            },
+            Err(error) => Self {
+                status: Status::Failed,
+                result: None,
+                error: Some(error),
+                execution_time,
+            },
+        }
+    }
+
+    /// The text the node's agent produced.
+    pub fn text(&self) -> Option<&str> {
+        self.result.as_ref().map(|result| result.text.as_str())
+    }
+}
+
+/// Progress of a multi-agent run. `R` is the pattern's result type.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MultiAgentEvent<R> {
+    /// A node started.
+    NodeStart { node_id: String },
+    /// An event from a running node's agent.
+    NodeStream { node_id: String, event: StreamEvent },
+    /// A node finished.
+    NodeStop { node_id: String, status: Status },
+    /// Control passed from some nodes to others.
+    Handoff {
+        from_node_ids: Vec<String>,
+        to_node_ids: Vec<String>,
+    },
+    /// The run finished. Always the last event.
+    Done(Box<R>),
+}
+
+type RunFuture<'a, R> = Pin<Box<dyn Future<Output = R> + Send + 'a>>;
+
+/// Events of a running orchestration, returned by [`Graph::stream`] and
+/// [`Swarm::stream`].
+pub struct MultiAgentStream<'a, R> {
+    run: Option<RunFuture<'a, R>>,
+    events: mpsc::Receiver<MultiAgentEvent<R>>,
+    outcome: Option<R>,
+}
+
+/// Sends node events to a [`MultiAgentStream`].
+type EventSender<R> = mpsc::Sender<MultiAgentEvent<R>>;
+
+impl<'a, R> MultiAgentStream<'a, R> {
+    /// Start a run. `run` receives the sender for its node events.
+    fn new<F>(run: impl FnOnce(EventSender<R>) -> F) -> Self
+    where
+        F: Future<Output = R> + Send + 'a,
+    {
+        let (sender, events) = mpsc::channel();
+        Self {
+            run: Some(Box::pin(run(sender))),
+            events,
+            outcome: None,
+        }
+    }
+}
+
+impl<R> futures_core::Stream for MultiAgentStream<'_, R> {
+    type Item = MultiAgentEvent<R>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Ok(event) = this.events.try_recv() {
+                return Poll::Ready(Some(event));
+            }
+            if let Some(outcome) = this.outcome.take() {
+                return Poll::Ready(Some(MultiAgentEvent::Done(Box::new(outcome))));
+            }
+            let Some(run) = &mut this.run else {
+                return Poll::Ready(None);
+            };
+            match run.as_mut().poll(cx) {
+                Poll::Ready(outcome) => {
+                    this.run = None;
+                    this.outcome = Some(outcome);
+                }
+                // The run may have sent events before suspending.
+                Poll::Pending => {
+                    return match this.events.try_recv() {
+                        Ok(event) => Poll::Ready(Some(event)),
+                        Err(_) => Poll::Pending,
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl<R> Unpin for MultiAgentStream<'_, R> {}
+
+/// Invoke a node's agent, reporting its start, events and stop.
+async fn run_node<R: Send>(
+    node_id: &str,
+    agent: &mut Agent,
+    prompt: &str,
+    tools: Option<Vec<ToolSpec>>,
+    events: &EventSender<R>,
+) -> NodeResult {
+    let _ = events.send(MultiAgentEvent::NodeStart {
+        node_id: node_id.to_string(),
+    });
+
+    let start = Instant::now();
+    let sender = events.clone();
+    let id = node_id.to_string();
+    let result = agent
+        .invoke_with_events(prompt, tools, move |event| {
+            let _ = sender.send(MultiAgentEvent::NodeStream {
+                node_id: id.clone(),
+                event: event.clone(),
+            });
+        })
+        .await;
+    let node_result = NodeResult::new(result, start.elapsed());
+
+    let _ = events.send(MultiAgentEvent::NodeStop {
+        node_id: node_id.to_string(),
+        status: node_result.status,
+    });
+    node_result
+}
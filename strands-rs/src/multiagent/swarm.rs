@@ -0,0 +1,604 @@
+//! Swarm orchestration.
+//!
+//! One agent runs at a time. Each agent's host tool dispatch is wrapped so
+//! calls to [`HANDOFF_TOOL`] are answered by the swarm instead of reaching the
+//! agent's own tools; the tool's spec is added to every invocation as a
+//! per-request tool override. When an agent finishes its turn after calling
+//! the tool, control passes to the named agent with the handoff message and
+//! the context shared so far. An agent that finishes without handing off ends
+//! the swarm.
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use serde_json::Value;
+
+use super::{EventSender, MultiAgentEvent, MultiAgentStream, NodeResult, Status, run_node};
+use crate::{
+    Agent, Error, HostState, Result, ToolContext, ToolDispatchFn, ToolFuture, ToolResult, ToolSpec,
+    Usage, result,
+};
+
+/// Name of the tool injected into every swarm agent.
+pub const HANDOFF_TOOL: &str = "handoff_to_agent";
+
+/// An event from a running [`Swarm`].
+pub type SwarmEvent = MultiAgentEvent<SwarmResult>;
+
+/// The event stream of a running [`Swarm`].
+pub type SwarmStream<'a> = MultiAgentStream<'a, SwarmResult>;
+
+/// A handoff requested through [`HANDOFF_TOOL`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handoff {
+    /// The agent taking over.
+    pub to: String,
+    /// What the next agent should do.
+    pub message: String,
+    /// Context the handing-off agent shared with the rest of the swarm.
+    pub context: Option<Value>,
+}
+
+/// One agent turn in a swarm run.
+#[derive(Debug)]
+pub struct SwarmTurn {
+    pub node_id: String,
+    /// The prompt the agent was given.
+    pub input: String,
+    pub result: NodeResult,
+    /// Where the agent handed control, if it did.
+    pub handoff: Option<Handoff>,
+}
+
+/// The result of a [`Swarm`] run.
+#[derive(Debug)]
+pub struct SwarmResult {
+    /// `Completed` when an agent finished without handing off.
+    pub status: Status,
+    /// Every turn, in order.
+    pub transcript: Vec<SwarmTurn>,
+    /// Why the swarm stopped early: a failed agent or a handoff limit.
+    pub error: Option<Error>,
+    /// Usage summed across every turn.
+    pub total_usage: Usage,
+    pub execution_time: Duration,
+}
+
+impl SwarmResult {
+    /// The last turn's text, i.e. the swarm's answer when it completed.
+    pub fn text(&self) -> Option<&str> {
+        self.transcript.last().and_then(|turn| turn.result.text())
+    }
+
+    /// IDs of the agents in the order they ran.
+    pub fn node_history(&self) -> Vec<&str> {
+        self.transcript
+            .iter()
+            .map(|turn| turn.node_id.as_str())
+            .collect()
+    }
+}
+
+struct Member {
+    id: String,
+    description: String,
+    agent: Agent,
+}
+
+/// Builder for [`Swarm`].
+pub struct SwarmBuilder {
+    members: Vec<Member>,
+    entry_point: Option<String>,
+    max_handoffs: usize,
+    max_ping_pong: usize,
+}
+
+impl SwarmBuilder {
+    /// Add a named agent. The description tells the other agents when to hand
+    /// off to it.
+    pub fn add_agent(
+        mut self,
+        id: impl Into<String>,
+        description: impl Into<String>,
+        agent: Agent,
+    ) -> Self {
+        self.members.push(Member {
+            id: id.into(),
+            description: description.into(),
+            agent,
+        });
+        self
+    }
+
+    /// The agent that receives the task. Defaults to the first agent added.
+    pub fn entry_point(mut self, id: impl Into<String>) -> Self {
+        self.entry_point = Some(id.into());
+        self
+    }
+
+    /// Most handoffs in one run before the swarm stops. Defaults to 20.
+    pub fn max_handoffs(mut self, max: usize) -> Self {
+        self.max_handoffs = max;
+        self
+    }
+
+    /// Most consecutive handoffs straight back to the agent that just handed
+    /// off, before the swarm stops as stuck. Defaults to 3.
+    pub fn max_ping_pong(mut self, max: usize) -> Self {
+        self.max_ping_pong = max;
+        self
+    }
+
+    /// Validate the swarm and install the handoff tool in every agent.
+    pub fn build(mut self) -> Result<Swarm> {
+        if self.members.is_empty() {
+            return Err(Error::MultiAgent("swarm has no agents".into()));
+        }
+        for (i, member) in self.members.iter().enumerate() {
+            if self.members[..i].iter().any(|m| m.id == member.id) {
+                return Err(Error::MultiAgent(format!(
+                    "duplicate agent '{}'",
+                    member.id
+                )));
+            }
+        }
+        if let Some(member) = self
+            .members
+            .iter()
+            .find(|m| claims_handoff(&m.agent.tools, m.agent.store.data()))
+        {
+            return Err(Error::MultiAgent(format!(
+                "agent '{}' has its own '{HANDOFF_TOOL}' tool, which would hide the handoff",
+                member.id
+            )));
+        }
+        let entry = match &self.entry_point {
+            Some(id) => self
+                .members
+                .iter()
+                .position(|m| &m.id == id)
+                .ok_or_else(|| Error::MultiAgent(format!("unknown agent '{id}'")))?,
+            None => 0,
+        };
+
+        let ids: Vec<String> = self.members.iter().map(|m| m.id.clone()).collect();
+        let requested = Arc::new(Mutex::new(None));
+        let mut handoff_specs = Vec::with_capacity(self.members.len());
+        for member in &mut self.members {
+            let others: Vec<String> = ids.iter().filter(|id| **id != member.id).cloned().collect();
+            handoff_specs.push(handoff_spec(&others));
+            let state = member.agent.store.data_mut();
+            state.tool_dispatch = Some(handoff_dispatch(
+                others,
+                Arc::clone(&requested),
+                state.tool_dispatch.take(),
+            ));
+        }
+
+        Ok(Swarm {
+            members: self.members,
+            handoff_specs,
+            entry,
+            requested,
+            max_handoffs: self.max_handoffs,
+            max_ping_pong: self.max_ping_pong,
+        })
+    }
+}
+
+/// A team of agents that pass control to each other.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::Agent;
+/// use strands::multiagent::Swarm;
+///
+/// let researcher = Agent::builder().system_prompt("You research topics.").build().await?;
+/// let coder = Agent::builder().system_prompt("You write Rust code.").build().await?;
+///
+/// let mut swarm = Swarm::builder()
+///     .add_agent("researcher", "Finds background information", researcher)
+///     .add_agent("coder", "Writes and explains code", coder)
+///     .max_handoffs(10)
+///     .build()?;
+///
+/// let result = swarm.invoke("Write a Rust function that computes CRC32").await;
+/// println!("{}", result.node_history().join(" -> "));
+/// println!("{}", result.text().unwrap_or_default());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Swarm {
+    members: Vec<Member>,
+    /// The handoff tool offered to each member, listing the other members.
+    handoff_specs: Vec<ToolSpec>,
+    entry: usize,
+    /// Set by the handoff tool during the running agent's turn.
+    requested: Arc<Mutex<Option<Handoff>>>,
+    max_handoffs: usize,
+    max_ping_pong: usize,
+}
+
+impl Swarm {
+    /// Create a builder for a swarm.
+    pub fn builder() -> SwarmBuilder {
+        SwarmBuilder {
+            members: Vec::new(),
+            entry_point: None,
+            max_handoffs: 20,
+            max_ping_pong: 3,
+        }
+    }
+
+    /// The agent with the given ID.
+    pub fn agent(&self, id: &str) -> Option<&Agent> {
+        self.members.iter().find(|m| m.id == id).map(|m| &m.agent)
+    }
+
+    /// The agent with the given ID, mutably.
+    ///
+    /// Agents keep the swarm's handoff dispatch once a run is over. Outside
+    /// the swarm the model is not offered [`HANDOFF_TOOL`], but a call to it
+    /// is still answered by the swarm and has no effect.
+    pub fn agent_mut(&mut self, id: &str) -> Option<&mut Agent> {
+        self.members
+            .iter_mut()
+            .find(|m| m.id == id)
+            .map(|m| &mut m.agent)
+    }
+
+    /// Run the swarm and stream node events, ending with
+    /// [`MultiAgentEvent::Done`].
+    pub fn stream<'a>(&'a mut self, task: &str) -> SwarmStream<'a> {
+        let task = task.to_string();
+        MultiAgentStream::new(move |events| self.run(task, events))
+    }
+
+    /// Run the swarm to completion.
+    pub async fn invoke(&mut self, task: &str) -> SwarmResult {
+        let mut stream = self.stream(task);
+        while let Some(event) = stream.next().await {
+            if let MultiAgentEvent::Done(result) = event {
+                return *result;
+            }
+        }
+        unreachable!("swarm stream ended without a result")
+    }
+
+    async fn run(&mut self, task: String, events: EventSender<SwarmResult>) -> SwarmResult {
+        let start = Instant::now();
+        let mut transcript: Vec<SwarmTurn> = Vec::new();
+        let mut current = self.entry;
+        let mut input = task.clone();
+        let mut limits = HandoffLimits::new(self.max_handoffs, self.max_ping_pong);
+
+        let (status, error) = loop {
+            take(&self.requested);
+            let member = &mut self.members[current];
+            let mut tools = member.agent.tools.clone();
+            tools.push(self.handoff_specs[current].clone());
+            let node_result =
+                run_node(&member.id, &mut member.agent, &input, Some(tools), &events).await;
+            let status = node_result.status;
+            let handoff = take(&self.requested);
+
+            transcript.push(SwarmTurn {
+                node_id: member.id.clone(),
+                input: std::mem::take(&mut input),
+                result: node_result,
+                handoff: handoff.clone(),
+            });
+            if status != Status::Completed {
+                break (status, None);
+            }
+            let Some(handoff) = handoff else {
+                break (Status::Completed, None);
+            };
+
+            let previous = transcript
+                .iter()
+                .rev()
+                .nth(1)
+                .map(|turn| turn.node_id.as_str());
+            if let Err(reason) = limits.record(&self.members[current].id, &handoff.to, previous) {
+                break (Status::Failed, Some(Error::MultiAgent(reason)));
+            }
+
+            let _ = events.send(MultiAgentEvent::Handoff {
+                from_node_ids: vec![self.members[current].id.clone()],
+                to_node_ids: vec![handoff.to.clone()],
+            });
+            current = self
+                .members
+                .iter()
+                .position(|m| m.id == handoff.to)
+                .expect("handoff targets are validated by the tool");
+            input = self.handoff_input(&task, current, &transcript);
+        };
+
+        let mut total_usage = result::empty_usage();
+        for turn in &transcript {
+            if let Some(agent_result) = &turn.result.result {
+                result::accumulate(&mut total_usage, &agent_result.total_usage);
+            }
+        }
+
+        SwarmResult {
+            status,
+            transcript,
+            error,
+            total_usage,
+            execution_time: start.elapsed(),
+        }
+    }
+
+    /// The prompt for the agent taking over: the handoff message, the original
+    /// task, who has worked on it, shared context and the other agents.
+    fn handoff_input(&self, task: &str, next: usize, transcript: &[SwarmTurn]) -> String {
+        let last = transcript.last().expect("a handoff follows a turn");
+        let handoff = last.handoff.as_ref().expect("the last turn handed off");
+
+        let mut input = format!(
+            "Handoff from {}: {}\n\nUser request: {task}\n",
+            last.node_id, handoff.message
+        );
+        let history: Vec<&str> = transcript.iter().map(|t| t.node_id.as_str()).collect();
+        input.push_str(&format!(
+            "\nPrevious agents who worked on this: {}\n",
+            history.join(" -> ")
+        ));
+
+        let shared: Vec<String> = transcript
+            .iter()
+            .filter_map(|turn| {
+                let context = turn.handoff.as_ref()?.context.as_ref()?;
+                Some(format!("- {}: {context}", turn.node_id))
+            })
+            .collect();
+        if !shared.is_empty() {
+            input.push_str("\nShared knowledge from previous agents:\n");
+            input.push_str(&shared.join("\n"));
+            input.push('\n');
+        }
+
+        input.push_str("\nOther agents available for collaboration:\n");
+        for (i, member) in self.members.iter().enumerate() {
+            if i != next {
+                input.push_str(&format!("- {}: {}\n", member.id, member.description));
+            }
+        }
+        input.push_str(&format!(
+            "\nCall {HANDOFF_TOOL} if another agent should continue. \
+             If you do not hand off, your answer completes the task."
+        ));
+        input
+    }
+}
+
+/// Take the handoff recorded by the running agent, if any.
+fn take(requested: &Mutex<Option<Handoff>>) -> Option<Handoff> {
+    requested
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+}
+
+/// The handoff tool offered to one agent, restricted to the other agents.
+fn handoff_spec(others: &[String]) -> ToolSpec {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "agent_name": {
+                "type": "string",
+                "enum": others,
+                "description": "The agent to hand off to.",
+            },
+            "message": {
+                "type": "string",
+                "description": "What the next agent should do.",
+            },
+            "context": {
+                "type": "object",
+                "description": "Information the next agent needs.",
+            },
+        },
+        "required": ["agent_name", "message"],
+    });
+    ToolSpec {
+        name: HANDOFF_TOOL.into(),
+        description: "Transfer control to another agent in the swarm. Use this when another \
+                      agent is better suited to continue the task."
+            .into(),
+        input_schema: schema.to_string(),
+    }
+}
+
+/// Whether an agent has a tool of its own named [`HANDOFF_TOOL`]. Its handler
+/// would be called before the swarm's dispatch.
+fn claims_handoff(tools: &[ToolSpec], state: &HostState) -> bool {
+    tools.iter().any(|tool| tool.name == HANDOFF_TOOL)
+        || state.tool_handlers.contains_key(HANDOFF_TOOL)
+}
+
+/// Wrap an agent's dispatch so handoff calls are recorded for the swarm and
+/// everything else reaches the agent's own tools.
+fn handoff_dispatch(
+    others: Vec<String>,
+    requested: Arc<Mutex<Option<Handoff>>>,
+    inner: Option<ToolDispatchFn>,
+) -> ToolDispatchFn {
+    Arc::new(
+        move |name: &str, input: &str, context: &ToolContext| -> ToolFuture {
+            if name != HANDOFF_TOOL {
+                return match &inner {
+                    Some(dispatch) => dispatch(name, input, context),
+                    None => {
+                        let err = format!("no handler for tool '{name}'");
+                        Box::pin(async move { Err(err) })
+                    }
+                };
+            }
+            let result = parse_handoff(input, &others).map(|handoff| {
+                let reply = format!("Handing off to {}", handoff.to);
+                *requested.lock().unwrap_or_else(PoisonError::into_inner) = Some(handoff);
+                ToolResult::success().text(reply).to_json()
+            });
+            Box::pin(async move { result })
+        },
+    )
+}
+
+/// Counts a run's handoffs against the swarm's limits.
+struct HandoffLimits {
+    max_handoffs: usize,
+    max_ping_pong: usize,
+    handoffs: usize,
+    bounces: usize,
+}
+
+impl HandoffLimits {
+    fn new(max_handoffs: usize, max_ping_pong: usize) -> Self {
+        Self {
+            max_handoffs,
+            max_ping_pong,
+            handoffs: 0,
+            bounces: 0,
+        }
+    }
+
+    /// Record a handoff from `from` to `to`, where `previous` ran before
+    /// `from`. Fails once either limit is exceeded.
+    fn record(&mut self, from: &str, to: &str, previous: Option<&str>) -> Result<(), String> {
+        self.handoffs += 1;
+        if self.handoffs > self.max_handoffs {
+            return Err(format!("handoff limit of {} reached", self.max_handoffs));
+        }
+        self.bounces = if previous == Some(to) {
+            self.bounces + 1
+        } else {
+            0
+        };
+        if self.bounces > self.max_ping_pong {
+            return Err(format!(
+                "'{from}' and '{to}' keep handing off to each other"
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parse_handoff(input: &str, others: &[String]) -> Result<Handoff, String> {
+    let input: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+    let to = input["agent_name"]
+        .as_str()
+        .ok_or("agent_name is required")?;
+    if !others.iter().any(|id| id == to) {
+        return Err(format!(
+            "unknown agent '{to}'; choose one of: {}",
+            others.join(", ")
+        ));
+    }
+    Ok(Handoff {
+        to: to.to_string(),
+        message: input["message"].as_str().unwrap_or_default().to_string(),
+        context: input.get("context").filter(|c| !c.is_null()).cloned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn others() -> Vec<String> {
+        vec!["researcher".into(), "writer".into()]
+    }
+
+    #[test]
+    fn own_handoff_tools_are_rejected() {
+        let spec = |name: &str| ToolSpec {
+            name: name.into(),
+            description: String::new(),
+            input_schema: "{}".into(),
+        };
+        let echo: crate::ToolHandlerFn = Arc::new(|input: &str, _: &ToolContext| -> ToolFuture {
+            let input = input.to_string();
+            Box::pin(async move { Ok(input) })
+        });
+        let plain = HostState::new();
+        let handled = HostState {
+            tool_handlers: Arc::new(std::collections::HashMap::from([(
+                HANDOFF_TOOL.to_string(),
+                echo,
+            )])),
+            ..HostState::new()
+        };
+
+        assert!(!claims_handoff(&[spec("search")], &plain));
+        assert!(claims_handoff(&[spec(HANDOFF_TOOL)], &plain));
+        assert!(claims_handoff(&[], &handled));
+    }
+
+    #[test]
+    fn parses_a_handoff() {
+        let input =
+            r#"{"agent_name": "writer", "message": "draft it", "context": {"topic": "tides"}}"#;
+        let handoff = parse_handoff(input, &others()).unwrap();
+        assert_eq!(
+            handoff,
+            Handoff {
+                to: "writer".into(),
+                message: "draft it".into(),
+                context: Some(serde_json::json!({"topic": "tides"})),
+            }
+        );
+    }
+
+    #[test]
+    fn drops_a_null_context() {
+        let input = r#"{"agent_name": "writer", "message": "", "context": null}"#;
+        assert_eq!(parse_handoff(input, &others()).unwrap().context, None);
+    }
+
+    #[test]
+    fn rejects_bad_handoffs() {
+        let unknown = parse_handoff(r#"{"agent_name": "editor"}"#, &others()).unwrap_err();
+        assert_eq!(
+            unknown,
+            "unknown agent 'editor'; choose one of: researcher, writer"
+        );
+        let missing = parse_handoff(r#"{"message": "hi"}"#, &others()).unwrap_err();
+        assert_eq!(missing, "agent_name is required");
+        assert!(parse_handoff("not json", &others()).is_err());
+    }
+
+    #[test]
+    fn stops_at_the_handoff_limit() {
+        let mut limits = HandoffLimits::new(2, 3);
+        assert!(limits.record("a", "b", None).is_ok());
+        assert!(limits.record("b", "c", Some("a")).is_ok());
+        let err = limits.record("c", "a", Some("b")).unwrap_err();
+        assert_eq!(err, "handoff limit of 2 reached");
+    }
+
+    #[test]
+    fn stops_agents_handing_off_back_and_forth() {
+        let mut limits = HandoffLimits::new(20, 2);
+        assert!(limits.record("a", "b", None).is_ok());
+        assert!(limits.record("b", "a", Some("a")).is_ok());
+        assert!(limits.record("a", "b", Some("b")).is_ok());
+        let err = limits.record("b", "a", Some("a")).unwrap_err();
+        assert_eq!(err, "'b' and 'a' keep handing off to each other");
+    }
+
+    #[test]
+    fn other_handoffs_reset_the_bounce_count() {
+        let mut limits = HandoffLimits::new(20, 1);
+        assert!(limits.record("a", "b", None).is_ok());
+        assert!(limits.record("b", "a", Some("a")).is_ok());
+        assert!(limits.record("a", "c", Some("b")).is_ok());
+        assert!(limits.record("c", "a", Some("a")).is_ok());
+        assert!(limits.record("a", "c", Some("c")).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! Tool permission policy.
+//!
+//! A [`ToolPolicy`] decides at the host boundary whether a tool call may run.
+//! Rules match tool names by glob and can be narrowed with predicates on the
+//! call's input, addressed by JSON pointer. The first matching rule wins;
+//! calls no rule matches get the policy's default permission.
+//!
+//! Input that is not valid JSON is treated as matching the conditions of deny
+//! and ask rules, never those of allow rules.
+//!
+//! A denied call never reaches the tool: the model receives an error result
+//! instead. A call that needs approval raises the same `tool_approval`
+//! [`Interrupt`](crate::Interrupt) as
+//! [`AgentBuilder::interrupt_before`](crate::AgentBuilder::interrupt_before),
+//! answered by the interrupt handler or through
+//! [`Agent::resume`](crate::Agent::resume).
+
+use serde_json::Value;
+
+/// What happens to a tool call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Permission {
+    /// Run the tool.
+    #[default]
+    Allow,
+    /// Refuse the call. The model receives an error result.
+    Deny,
+    /// Run the tool only once the call is approved.
+    Ask,
+}
+
+/// A test on the input value at a JSON pointer. A pointer that does not
+/// resolve fails every predicate except [`Absent`](Self::Absent).
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Predicate {
+    /// The pointer resolves.
+    Exists,
+    /// The pointer does not resolve.
+    Absent,
+    /// The value equals this one.
+    Equals(Value),
+    /// The value equals one of these.
+    OneOf(Vec<Value>),
+    /// The value is a string matching this glob (`*` and `?` wildcards).
+    Glob(String),
+    /// The value is a number no greater than this.
+    AtMost(f64),
+    /// The value is a number no less than this.
+    AtLeast(f64),
+    /// The inner predicate fails.
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    fn test(&self, value: Option<&Value>) -> bool {
+        match (self, value) {
+            (Self::Absent, value) => value.is_none(),
+            (Self::Not(inner), value) => !inner.test(value),
+            (_, None) => false,
+            (Self::Exists, Some(_)) => true,
+            (Self::Equals(expected), Some(value)) => value == expected,
+            (Self::OneOf(options), Some(value)) => options.contains(value),
+            (Self::Glob(pattern), Some(value)) => {
+                value.as_str().is_some_and(|s| glob_match(pattern, s))
+            }
+            (Self::AtMost(max), Some(value)) => value.as_f64().is_some_and(|n| n <= *max),
+            (Self::AtLeast(min), Some(value)) => value.as_f64().is_some_and(|n| n >= *min),
+        }
+    }
+}
+
+/// A permission for the tools whose names match a glob, optionally limited
+/// to calls whose input satisfies every condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolRule {
+    pattern: String,
+    permission: Permission,
+    conditions: Vec<(String, Predicate)>,
+}
+
+impl ToolRule {
+    pub fn new(pattern: impl Into<String>, permission: Permission) -> Self {
+        Self {
+            pattern: pattern.into(),
+            permission,
+            conditions: Vec::new(),
+        }
+    }
+
+    pub fn allow(pattern: impl Into<String>) -> Self {
+        Self::new(pattern, Permission::Allow)
+    }
+
+    pub fn deny(pattern: impl Into<String>) -> Self {
+        Self::new(pattern, Permission::Deny)
+    }
+
+    pub fn ask(pattern: impl Into<String>) -> Self {
+        Self::new(pattern, Permission::Ask)
+    }
+
+    /// Only apply the rule if the input value at `pointer` (e.g. `/path`)
+    /// satisfies `predicate`.
+    pub fn when(mut self, pointer: impl Into<String>, predicate: Predicate) -> Self {
+        self.conditions.push((pointer.into(), predicate));
+        self
+    }
+
+    fn matches(&self, tool: &str, input: &Value) -> bool {
+        glob_match(&self.pattern, tool)
+            && self
+                .conditions
+                .iter()
+                .all(|(pointer, predicate)| predicate.test(input.pointer(pointer)))
+    }
+}
+
+/// Allow, deny and require-approval rules for tool calls.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::{Agent, Permission, Predicate, ToolPolicy, ToolRule};
+///
+/// let policy = ToolPolicy::new()
+///     .rule(ToolRule::allow("read_*").when("/path", Predicate::Glob("/srv/data/*".into())))
+///     .ask("write_*")
+///     .deny("shell")
+///     .default_permission(Permission::Deny);
+///
+/// let agent = Agent::builder()
+///     .tool_policy(policy)
+///     .interrupt_handler(|interrupt| async move {
+///         println!("approve {}?", interrupt.reason);
+///         Some(serde_json::Value::Bool(true))
+///     })
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolPolicy {
+    rules: Vec<ToolRule>,
+    default: Permission,
+}
+
+impl ToolPolicy {
+    /// A policy with no rules that allows every call.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule. Rules are checked in the order they were added.
+    pub fn rule(mut self, rule: ToolRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Allow every call to the matching tools.
+    pub fn allow(self, pattern: impl Into<String>) -> Self {
+        self.rule(ToolRule::allow(pattern))
+    }
+
+    /// Deny every call to the matching tools.
+    pub fn deny(self, pattern: impl Into<String>) -> Self {
+        self.rule(ToolRule::deny(pattern))
+    }
+
+    /// Require approval for every call to the matching tools.
+    pub fn ask(self, pattern: impl Into<String>) -> Self {
+        self.rule(ToolRule::ask(pattern))
+    }
+
+    /// The permission for calls no rule matches. Defaults to
+    /// [`Permission::Allow`].
+    pub fn default_permission(mut self, permission: Permission) -> Self {
+        self.default = permission;
+        self
+    }
+
+    /// The permission for a call to `tool` with `input`.
+    pub fn check(&self, tool: &str, input: &Value) -> Permission {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(tool, input))
+            .map_or(self.default, |rule| rule.permission)
+    }
+
+    /// The permission for a call to `tool` with raw JSON `input`. Input that
+    /// does not parse satisfies the conditions of deny and ask rules but not
+    /// those of allow rules, so it cannot slip past a conditioned restriction.
+    pub fn check_json(&self, tool: &str, input: &str) -> Permission {
+        if let Ok(input) = serde_json::from_str(input) {
+            return self.check(tool, &input);
+        }
+        self.rules
+            .iter()
+            .find(|rule| {
+                glob_match(&rule.pattern, tool)
+                    && (rule.conditions.is_empty() || rule.permission != Permission::Allow)
+            })
+            .map_or(self.default, |rule| rule.permission)
+    }
+}
+
+/// Match `text` against a glob where `*` matches any run of characters and
+/// `?` matches exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`: its position and the text it
+    // currently ends at.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob_match("read_*", "read_file"));
+        assert!(glob_match("read_*", "read_"));
+        assert!(!glob_match("read_*", "write_file"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("file_?", "file_a"));
+        assert!(!glob_match("file_?", "file_"));
+        assert!(!glob_match("file_?", "file_ab"));
+        assert!(glob_match("/srv/*/logs/*", "/srv/app/logs/today"));
+        assert!(glob_match("*a*b", "xxaxxab"));
+        assert!(!glob_match("*a*b", "xxaxxa"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+    }
+
+    #[test]
+    fn predicates_on_present_values() {
+        let value = json!({"path": "/srv/data/a", "size": 10});
+        let at = |pointer| value.pointer(pointer);
+        assert!(Predicate::Exists.test(at("/path")));
+        assert!(!Predicate::Absent.test(at("/path")));
+        assert!(Predicate::Equals(json!(10)).test(at("/size")));
+        assert!(Predicate::OneOf(vec![json!(1), json!(10)]).test(at("/size")));
+        assert!(!Predicate::OneOf(vec![json!(1)]).test(at("/size")));
+        assert!(Predicate::Glob("/srv/*".into()).test(at("/path")));
+        assert!(!Predicate::Glob("*".into()).test(at("/size")));
+        assert!(Predicate::AtMost(10.0).test(at("/size")));
+        assert!(!Predicate::AtMost(9.0).test(at("/size")));
+        assert!(Predicate::AtLeast(10.0).test(at("/size")));
+        assert!(!Predicate::AtLeast(10.0).test(at("/path")));
+        assert!(Predicate::Not(Box::new(Predicate::Equals(json!(3)))).test(at("/size")));
+    }
+
+    #[test]
+    fn predicates_on_missing_values() {
+        assert!(Predicate::Absent.test(None));
+        assert!(!Predicate::Exists.test(None));
+        assert!(!Predicate::Equals(Value::Null).test(None));
+        assert!(!Predicate::AtLeast(0.0).test(None));
+        assert!(Predicate::Not(Box::new(Predicate::Exists)).test(None));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let policy = ToolPolicy::new()
+            .rule(ToolRule::allow("read_*").when("/path", Predicate::Glob("/srv/*".into())))
+            .ask("read_*")
+            .default_permission(Permission::Deny);
+        let check = |tool, input| policy.check(tool, &input);
+        assert_eq!(
+            check("read_file", json!({"path": "/srv/a"})),
+            Permission::Allow
+        );
+        assert_eq!(
+            check("read_file", json!({"path": "/etc/a"})),
+            Permission::Ask
+        );
+        assert_eq!(check("shell", json!({})), Permission::Deny);
+    }
+
+    #[test]
+    fn unparseable_input_meets_restrictions_only() {
+        let policy = ToolPolicy::new()
+            .rule(ToolRule::deny("shell").when("/cmd", Predicate::Glob("rm *".into())))
+            .rule(ToolRule::allow("read").when("/path", Predicate::Glob("/srv/*".into())))
+            .ask("read");
+        assert_eq!(policy.check_json("shell", "{not json"), Permission::Deny);
+        assert_eq!(policy.check_json("read", "{not json"), Permission::Ask);
+        assert_eq!(policy.check_json("other", "{not json"), Permission::Allow);
+        assert_eq!(
+            policy.check_json("shell", r#"{"cmd": "ls"}"#),
+            Permission::Allow
+        );
+        assert_eq!(
+            policy.check_json("shell", r#"{"cmd": "rm -rf"}"#),
+            Permission::Deny
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Pools of warm agents for server workloads.
+//!
+//! An [`AgentPool`] owns its own engine configured with wasmtime's pooling
+//! instance allocator, which reserves instance, memory, table and stack slots
+//! up front so instantiation is mostly a matter of resetting a slot. The
+//! component is linked once into an `InstancePre`, and `size` agents are built
+//! ahead of time so checkout only pops an idle agent.
+//!
+//! A [`PooledAgent`] returns to the pool when dropped. Resetting its
+//! conversation needs a guest call, so that happens on a background task and
+//! the slot frees up once the reset finishes; an agent that fails to reset is
+//! discarded and replaced on a later checkout. Resetting also restores the
+//! tools the agent was built with, so tools one user adds or removes do not
+//! carry over to the next. Agents that leave the pool,
+//! detached or forked, move to their own instance outside the pool's slots.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use wasmtime::component::Component;
+use wasmtime::{Engine, InstanceAllocationStrategy, PoolingAllocationConfig};
+
+use crate::resilience::ToolGuard;
+use crate::{
+    Agent, AgentBuilder, Error, HostState, Result, Runtime, Scratch, ToolHandlerFn, ToolSpec,
+    bindings, deadline, validation,
+};
+
+/// Upper bounds on what one agent instantiates. The bundled component creates
+/// a handful of core instances (the guest plus WASI adapters and shims).
+const CORE_INSTANCES_PER_AGENT: u32 = 16;
+const MEMORIES_PER_AGENT: u32 = 4;
+const TABLES_PER_AGENT: u32 = 16;
+
+/// Builds the [`AgentBuilder`] for each agent in the pool.
+type BuilderFactory = Box<dyn Fn() -> AgentBuilder + Send + Sync>;
+
+/// Builder for [`AgentPool`].
+pub struct AgentPoolBuilder {
+    factory: BuilderFactory,
+    size: usize,
+    max_memory_size: Option<usize>,
+}
+
+impl AgentPoolBuilder {
+    /// Number of agents kept warm, and the most that can be checked out at
+    /// once. Defaults to 4.
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = size.max(1);
+        self
+    }
+
+    /// Largest size any single linear memory may grow to. Defaults to 4 GiB.
+    pub fn max_memory_size(mut self, bytes: usize) -> Self {
+        self.max_memory_size = Some(bytes);
+        self
+    }
+
+    /// Create the engine, link the component and build the warm agents.
+    pub async fn build(self) -> Result<AgentPool> {
+        // Twice the pool size, so a poisoned agent can be rebuilt before its
+        // old instance is freed.
+        let slots = u32::try_from(self.size.saturating_mul(2)).unwrap_or(u32::MAX);
+        let mut pooling = PoolingAllocationConfig::new();
+        pooling
+            .total_component_instances(slots)
+            .total_core_instances(slots.saturating_mul(CORE_INSTANCES_PER_AGENT))
+            .total_memories(slots.saturating_mul(MEMORIES_PER_AGENT))
+            .total_tables(slots.saturating_mul(TABLES_PER_AGENT))
+            .total_stacks(slots)
+            .max_core_instances_per_component(CORE_INSTANCES_PER_AGENT)
+            .max_memories_per_component(MEMORIES_PER_AGENT)
+            .max_tables_per_component(TABLES_PER_AGENT)
+            .max_memories_per_module(MEMORIES_PER_AGENT)
+            .max_tables_per_module(TABLES_PER_AGENT)
+            .max_core_instance_size(16 << 20)
+            .max_component_instance_size(1 << 20)
+            .table_elements(100_000);
+        if let Some(bytes) = self.max_memory_size {
+            pooling.max_memory_size(bytes);
+        }
+
+        // Every agent comes from the same factory, so the first builder decides
+        // whether the pool meters fuel.
+        let first = (self.factory)();
+        let metered = first.limits.fuel_per_invocation.is_some();
+        let mut config = crate::wasm_config(metered);
+        config.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+        let engine = Engine::new(&config).map_err(|e| Error::ComponentLoad(format!("{e:#}")))?;
+        deadline::start_epoch_ticker(&engine);
+
+        let pre = unsafe { Component::deserialize(&engine, crate::aot_component(metered)?) }
+            .and_then(|component| crate::new_linker(&engine)?.instantiate_pre(&component))
+            .and_then(bindings::AgentPre::new)
+            .map_err(|e| Error::ComponentLoad(format!("{e:#}")))?;
+
+        let inner = Arc::new(PoolInner {
+            pre,
+            factory: self.factory,
+            idle: Mutex::new(Vec::with_capacity(self.size)),
+            permits: Arc::new(Semaphore::new(self.size)),
+            size: self.size,
+        });
+        let mut first = Some(first);
+        for _ in 0..self.size {
+            let builder = first.take().unwrap_or_else(|| (inner.factory)());
+            let entry = inner.build_agent_from(builder).await?;
+            inner.release(entry);
+        }
+        Ok(AgentPool { inner })
+    }
+}
+
+/// A fixed-size pool of pre-instantiated agents.
+///
+/// Every agent is built from the same [`AgentBuilder`], produced by the
+/// factory passed to [`AgentPool::builder`]. The builder's
+/// [`use_jit`](AgentBuilder::use_jit) setting is ignored; pooled agents always
+/// use the precompiled component.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::{Agent, AgentPool};
+///
+/// let pool = AgentPool::builder(|| {
+///     Agent::builder().system_prompt("You are a helpful assistant. Be concise.")
+/// })
+/// .size(16)
+/// .build()
+/// .await?;
+///
+/// let mut agent = pool.checkout().await?;
+/// let result = agent.invoke("Hello!").await?;
+/// println!("{}", result.text);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AgentPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    pre: bindings::AgentPre<crate::HostState>,
+    factory: BuilderFactory,
+    idle: Mutex<Vec<Entry>>,
+    permits: Arc<Semaphore>,
+    size: usize,
+}
+
+impl AgentPool {
+    /// Create a pool builder. `factory` is called once per agent.
+    pub fn builder(factory: impl Fn() -> AgentBuilder + Send + Sync + 'static) -> AgentPoolBuilder {
+        AgentPoolBuilder {
+            factory: Box::new(factory),
+            size: 4,
+            max_memory_size: None,
+        }
+    }
+
+    /// Check out an agent with an empty conversation, waiting if all agents
+    /// are in use.
+    pub async fn checkout(&self) -> Result<PooledAgent> {
+        let permit = Arc::clone(&self.inner.permits)
+            .acquire_owned()
+            .await
+            .expect("pool semaphore is never closed");
+        let idle = self
+            .inner
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        let entry = match idle {
+            Some(entry) => entry,
+            None => self.inner.build_agent().await?,
+        };
+        Ok(PooledAgent {
+            entry: Some(entry),
+            pool: Arc::clone(&self.inner),
+            permit: Some(permit),
+        })
+    }
+
+    /// Check out an idle agent without waiting. Returns `None` if none is
+    /// idle.
+    pub fn try_checkout(&self) -> Option<PooledAgent> {
+        let permit = Arc::clone(&self.inner.permits).try_acquire_owned().ok()?;
+        let entry = self
+            .inner
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()?;
+        Some(PooledAgent {
+            entry: Some(entry),
+            pool: Arc::clone(&self.inner),
+            permit: Some(permit),
+        })
+    }
+
+    /// The number of agents the pool holds.
+    pub fn size(&self) -> usize {
+        self.inner.size
+    }
+
+    /// The number of checkouts that can start without waiting for an agent to
+    /// be returned.
+    pub fn available(&self) -> usize {
+        self.inner.permits.available_permits()
+    }
+}
+
+impl PoolInner {
+    async fn build_agent(&self) -> Result<Entry> {
+        self.build_agent_from((self.factory)()).await
+    }
+
+    async fn build_agent_from(&self, builder: AgentBuilder) -> Result<Entry> {
+        let agent = Agent::from_builder(builder, Runtime::Pooled(self.pre.clone())).await?;
+        let tools = BuilderTools::of(&agent);
+        Ok(Entry { agent, tools })
+    }
+
+    fn release(&self, entry: Entry) {
+        self.idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(entry);
+    }
+}
+
+/// A pooled agent and the tools it was built with.
+struct Entry {
+    agent: Agent,
+    tools: BuilderTools,
+}
+
+/// The tools an agent's builder registered. Holding the tables keeps them
+/// from being changed in place: adding or removing a tool copies them.
+struct BuilderTools {
+    specs: Vec<ToolSpec>,
+    handlers: Arc<HashMap<String, ToolHandlerFn>>,
+    inputs: Arc<validation::InputValidators>,
+}
+
+impl BuilderTools {
+    fn of(agent: &Agent) -> Self {
+        let state = agent.store.data();
+        Self {
+            specs: agent.tools.clone(),
+            handlers: Arc::clone(&state.tool_handlers),
+            inputs: Arc::clone(&state.tool_inputs),
+        }
+    }
+}
+
+/// An agent checked out of an [`AgentPool`]. Dereferences to [`Agent`] and
+/// returns to the pool when dropped.
+pub struct PooledAgent {
+    entry: Option<Entry>,
+    pool: Arc<PoolInner>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PooledAgent {
+    /// Remove the agent from the pool, keeping its conversation, session and
+    /// scratch. It moves to an instance of its own, freeing its pool slot, and a
+    /// replacement is built on a later checkout. An interrupted invocation is
+    /// abandoned.
+    pub async fn detach(mut self) -> Result<Agent> {
+        let Entry { mut agent, .. } = self.entry.take().expect("pooled agent already taken");
+        agent.rehost(agent.runtime.unpooled()).await?;
+        Ok(agent)
+    }
+}
+
+impl Deref for PooledAgent {
+    type Target = Agent;
+
+    fn deref(&self) -> &Agent {
+        &self
+            .entry
+            .as_ref()
+            .expect("pooled agent already taken")
+            .agent
+    }
+}
+
+impl DerefMut for PooledAgent {
+    fn deref_mut(&mut self) -> &mut Agent {
+        &mut self
+            .entry
+            .as_mut()
+            .expect("pooled agent already taken")
+            .agent
+    }
+}
+
+impl Drop for PooledAgent {
+    fn drop(&mut self) {
+        let (Some(mut entry), Some(permit)) = (self.entry.take(), self.permit.take()) else {
+            return;
+        };
+        // Without a runtime the agent cannot be reset; let it go and build a
+        // fresh one on a later checkout.
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = Arc::clone(&self.pool);
+        runtime.spawn(async move {
+            if entry.agent.reset(&entry.tools).await.is_ok() {
+                pool.release(entry);
+            }
+            drop(permit);
+        });
+    }
+}
+
+impl Agent {
+    /// Move the agent to a new instance from `runtime`, keeping its
+    /// configuration, history and scratch.
+    async fn rehost(&mut self, runtime: Runtime) -> Result<()> {
+        let history = self.get_messages().await?;
+        self.abandon_suspended();
+        self.release_abandoned_streams().await?;
+        let host_state = self.store.data_mut().renew();
+        let (store, instance, wit_agent) =
+            crate::instantiate(host_state, &runtime, &self.config).await?;
+        self.store = store;
+        self.instance = instance;
+        self.wit_agent = wit_agent;
+        self.runtime = runtime;
+        self.set_messages(&history).await
+    }
+
+    /// Return the agent to a fresh conversation and its builder's tools for
+    /// its next user.
+    async fn reset(&mut self, tools: &BuilderTools) -> Result<()> {
+        self.checkpoint = None;
+        self.recover().await?;
+        self.abandon_suspended();
+        self.release_abandoned_streams().await?;
+        self.store.data_mut().forget_tenant();
+        self.restore_tools(tools).await?;
+        self.set_messages("[]").await
+    }
+
+    /// Put back the builder's tools if a user added or removed any.
+    async fn restore_tools(&mut self, tools: &BuilderTools) -> Result<()> {
+        let state = self.store.data();
+        if Arc::ptr_eq(&state.tool_handlers, &tools.handlers)
+            && Arc::ptr_eq(&state.tool_inputs, &tools.inputs)
+        {
+            return Ok(());
+        }
+        self.set_tools(tools.specs.clone()).await?;
+        let state = self.store.data_mut();
+        state.tool_handlers = Arc::clone(&tools.handlers);
+        state.tool_inputs = Arc::clone(&tools.inputs);
+        Ok(())
+    }
+}
+
+impl HostState {
+    /// Drop what the previous user can still reach. Scratch handles and
+    /// tool contexts they kept point at a scratch space the next user never
+    /// sees, and circuits their failures opened start closed.
+    fn forget_tenant(&mut self) {
+        self.interrupts.clear();
+        self.scratch = Scratch::default();
+        self.tool_guard = self.tool_guard.as_ref().map(ToolGuard::fresh);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{CircuitBreaker, ToolContext, ToolFuture, ToolOptions};
+
+    #[test]
+    fn next_user_cannot_reach_kept_scratch() {
+        let mut state = HostState::new();
+        state.scratch.set("user", "ada").unwrap();
+        let kept = state.scratch.clone();
+        let context = state.tool_context("call-1", None);
+
+        state.forget_tenant();
+
+        assert_eq!(state.scratch.get_value("user"), None);
+        kept.set("note", "left behind").unwrap();
+        assert_eq!(state.scratch.get_value("note"), None);
+        assert_eq!(
+            state.tool_context("call-2", None).scratch_value("note"),
+            None
+        );
+        assert_eq!(context.scratch::<String>("user").as_deref(), Some("ada"));
+    }
+
+    #[tokio::test]
+    async fn next_user_starts_with_closed_circuits() {
+        let options = ToolOptions {
+            circuit_breaker: Some(CircuitBreaker {
+                failure_threshold: 1,
+                cooldown: Duration::from_secs(60),
+            }),
+            ..Default::default()
+        };
+        let mut state = HostState {
+            tool_guard: Some(ToolGuard::new(HashMap::from([("flaky".into(), options)]))),
+            ..HostState::new()
+        };
+        let failing: ToolHandlerFn = Arc::new(|_: &str, _: &ToolContext| -> ToolFuture {
+            Box::pin(async { Err("down".into()) })
+        });
+        let call = |state: &HostState| {
+            let guard = state.tool_guard.as_ref().unwrap();
+            guard.call(
+                "flaky",
+                Arc::clone(&failing),
+                "{}",
+                &state.tool_context("call", None),
+            )
+        };
+
+        assert_eq!(call(&state).await, Err("down".into()));
+        assert!(call(&state).await.unwrap().contains("unavailable"));
+
+        state.forget_tenant();
+        assert_eq!(call(&state).await, Err("down".into()));
+    }
+}
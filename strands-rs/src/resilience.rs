@@ -0,0 +1,453 @@
+//! Per-tool timeouts, retries and circuit breaking.
+//!
+//! Calls to tools with [`ToolOptions`] go through a guard as they are
+//! dispatched. Each attempt is bounded by the tool's timeout, errors the
+//! handler marked with [`retryable`] are retried with exponential backoff, and
+//! a tool that keeps failing has its circuit opened so calls fail immediately
+//! until a cooldown passes. Both failed calls and error results count against
+//! the circuit.
+//!
+//! Timeouts and open circuits are tool errors: the model receives them as
+//! error results, and they appear in the stream as
+//! [`StreamEvent::ToolResult`](crate::StreamEvent::ToolResult) events with
+//! status `error`. [`ToolResult::failure`] tells them apart from errors the
+//! tool reported.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{ToolContext, ToolFuture, ToolHandlerFn, ToolResult, ToolResultStatus};
+
+/// Prefix marking a handler error as worth retrying.
+const RETRYABLE: &str = "retryable: ";
+
+/// Mark a tool handler error as transient, so it is retried if the tool has
+/// [`ToolOptions::retries`] left.
+///
+/// # Example
+/// ```no_run
+/// fn handler(_input: &str) -> Result<String, String> {
+///     Err(strands::retryable("rate limited"))
+/// }
+/// ```
+pub fn retryable(message: impl fmt::Display) -> String {
+    format!("{RETRYABLE}{message}")
+}
+
+/// Remove the [`retryable`] mark from a handler error.
+pub(crate) fn strip_retryable(error: String) -> String {
+    match error.strip_prefix(RETRYABLE) {
+        Some(message) => message.to_string(),
+        None => error,
+    }
+}
+
+/// Why a call failed without the tool producing a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ToolFailure {
+    /// An attempt ran past [`ToolOptions::timeout`].
+    Timeout,
+    /// The tool's [`CircuitBreaker`] was open.
+    CircuitOpen,
+}
+
+/// How calls to one tool are bounded and retried.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use std::time::Duration;
+/// use strands::{Agent, CircuitBreaker, ToolOptions};
+///
+/// let agent = Agent::builder()
+///     .tool_options(
+///         "search",
+///         ToolOptions {
+///             timeout: Some(Duration::from_secs(10)),
+///             retries: 2,
+///             circuit_breaker: Some(CircuitBreaker::default()),
+///             ..Default::default()
+///         },
+///     )
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ToolOptions {
+    /// How long one attempt may run. Only async handlers can be interrupted;
+    /// a synchronous handler finishes before its timeout starts.
+    pub timeout: Option<Duration>,
+    /// How many times to retry an error marked with [`retryable`]. Timeouts
+    /// are not retried.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each retry after it.
+    /// Defaults to 250ms.
+    pub backoff: Duration,
+    /// Stop calling the tool for a while after repeated failures. Off by
+    /// default.
+    pub circuit_breaker: Option<CircuitBreaker>,
+}
+
+impl Default for ToolOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            retries: 0,
+            backoff: Duration::from_millis(250),
+            circuit_breaker: None,
+        }
+    }
+}
+
+/// Stops calling a tool after repeated failures.
+///
+/// After `failure_threshold` consecutive failed calls the circuit opens and
+/// calls fail without running the tool. Once `cooldown` passes, calls run
+/// again; the first success closes the circuit, a failure reopens it.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit. Defaults to 5.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open. Defaults to 30 seconds.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Failure count and open deadline of one tool's circuit.
+#[derive(Default)]
+struct Circuit {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+type Circuits = Arc<Mutex<HashMap<String, Circuit>>>;
+
+/// Bounds, retries and circuit breaks calls to the tools that have
+/// [`ToolOptions`].
+#[derive(Clone)]
+pub(crate) struct ToolGuard {
+    options: Arc<HashMap<String, ToolOptions>>,
+    circuits: Circuits,
+}
+
+impl ToolGuard {
+    pub(crate) fn new(options: HashMap<String, ToolOptions>) -> Self {
+        Self {
+            options: Arc::new(options),
+            circuits: Circuits::default(),
+        }
+    }
+
+    /// The same options with every circuit closed.
+    pub(crate) fn fresh(&self) -> Self {
+        Self {
+            options: Arc::clone(&self.options),
+            circuits: Circuits::default(),
+        }
+    }
+
+    /// Call `handler` for the tool `name`, bounded by the tool's options.
+    pub(crate) fn call(
+        &self,
+        name: &str,
+        handler: ToolHandlerFn,
+        input: &str,
+        context: &ToolContext,
+    ) -> ToolFuture {
+        let Some(opts) = self.options.get(name).cloned() else {
+            return handler(input, context);
+        };
+        let circuits = Arc::clone(&self.circuits);
+        let (name, input, context) = (name.to_string(), input.to_string(), context.clone());
+        Box::pin(async move {
+            if let Some(remaining) = open_for(&circuits, &name) {
+                let message = format!(
+                    "tool '{name}' is unavailable after repeated failures; try again in {}s",
+                    remaining.as_secs().max(1)
+                );
+                return Ok(ToolResult::failed(ToolFailure::CircuitOpen, message).to_json());
+            }
+            let mut attempt = 0;
+            let result = loop {
+                let call = handler(&input, &context);
+                let result = match opts.timeout {
+                    Some(timeout) => {
+                        tokio::time::timeout(timeout, call)
+                            .await
+                            .unwrap_or_else(|_| {
+                                let message = format!("tool '{name}' timed out after {timeout:?}");
+                                Ok(ToolResult::failed(ToolFailure::Timeout, message).to_json())
+                            })
+                    }
+                    None => call.await,
+                };
+                match result {
+                    Err(e) if attempt < opts.retries && e.starts_with(RETRYABLE) => {
+                        tracing::debug!(target: "strands::tools", "retrying '{name}': {e}");
+                        tokio::time::sleep(opts.backoff.saturating_mul(1 << attempt.min(16))).await;
+                        attempt += 1;
+                    }
+                    result => break result,
+                }
+            };
+            if let Some(breaker) = &opts.circuit_breaker {
+                let succeeded = result
+                    .as_deref()
+                    .is_ok_and(|output| !is_error_result(output));
+                record(&circuits, &name, breaker, succeeded);
+            }
+            result
+        })
+    }
+}
+
+/// Whether handler output is a [`ToolResult`] with status `error`.
+fn is_error_result(output: &str) -> bool {
+    let Ok(Value::Object(result)) = serde_json::from_str(output) else {
+        return false;
+    };
+    result.contains_key("content")
+        && result
+            .get("status")
+            .and_then(|status| serde_json::from_value(status.clone()).ok())
+            == Some(ToolResultStatus::Error)
+}
+
+/// How much longer the tool's circuit stays open, if it is open.
+fn open_for(circuits: &Circuits, name: &str) -> Option<Duration> {
+    let circuits = circuits.lock().unwrap_or_else(PoisonError::into_inner);
+    let open_until = circuits.get(name)?.open_until?;
+    open_until.checked_duration_since(Instant::now())
+}
+
+/// Count a finished call against the tool's circuit.
+fn record(circuits: &Circuits, name: &str, breaker: &CircuitBreaker, succeeded: bool) {
+    let mut circuits = circuits.lock().unwrap_or_else(PoisonError::into_inner);
+    let circuit = circuits.entry(name.to_string()).or_default();
+    if succeeded {
+        *circuit = Circuit::default();
+        return;
+    }
+    circuit.failures += 1;
+    if circuit.failures >= breaker.failure_threshold {
+        tracing::warn!(
+            target: "strands::tools",
+            "opening circuit for '{name}' after {} consecutive failures",
+            circuit.failures
+        );
+        circuit.open_until = Some(Instant::now() + breaker.cooldown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::HostState;
+
+    /// A guard for the tool `tool` and a handler that counts its calls and
+    /// answers the `n`th with `answer(n)`.
+    fn guarded(
+        options: ToolOptions,
+        answer: impl Fn(u32) -> Result<String, String> + Send + Sync + 'static,
+    ) -> (impl Fn() -> ToolFuture, Arc<AtomicU32>) {
+        let guard = ToolGuard::new(HashMap::from([("tool".into(), options)]));
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let answer = Arc::new(answer);
+        let handler: ToolHandlerFn = Arc::new(move |_: &str, _: &ToolContext| -> ToolFuture {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            let answer = Arc::clone(&answer);
+            Box::pin(async move { answer(n) })
+        });
+        let context = HostState::new().tool_context("call", None);
+        let call = move || guard.call("tool", Arc::clone(&handler), "{}", &context);
+        (call, calls)
+    }
+
+    fn breaker(failure_threshold: u32, cooldown: Duration) -> ToolOptions {
+        ToolOptions {
+            circuit_breaker: Some(CircuitBreaker {
+                failure_threshold,
+                cooldown,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn failure(output: &Result<String, String>) -> Option<ToolFailure> {
+        let output = output.as_deref().ok()?;
+        serde_json::from_str::<ToolResult>(output).ok()?.failure()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tools_without_options_are_called_directly() {
+        let guard = ToolGuard::new(HashMap::new());
+        let handler: ToolHandlerFn = Arc::new(|_: &str, _: &ToolContext| -> ToolFuture {
+            Box::pin(async { Err(retryable("busy")) })
+        });
+        let context = HostState::new().tool_context("call", None);
+        let result = guard.call("other", handler, "{}", &context).await;
+        assert_eq!(result, Err(retryable("busy")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn times_out_slow_attempts_without_retrying() {
+        let options = ToolOptions {
+            timeout: Some(Duration::from_secs(1)),
+            retries: 2,
+            ..Default::default()
+        };
+        let guard = ToolGuard::new(HashMap::from([("tool".into(), options)]));
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let handler: ToolHandlerFn = Arc::new(move |_: &str, _: &ToolContext| -> ToolFuture {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok("late".into())
+            })
+        });
+        let context = HostState::new().tool_context("call", None);
+
+        let start = Instant::now();
+        let result = guard.call("tool", handler, "{}", &context).await;
+        assert_eq!(failure(&result), Some(ToolFailure::Timeout));
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_only_errors_marked_retryable() {
+        let options = ToolOptions {
+            retries: 3,
+            ..Default::default()
+        };
+        let (call, calls) = guarded(options.clone(), |_| Err("bad input".into()));
+        assert_eq!(call().await, Err("bad input".into()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (call, calls) = guarded(options, |n| match n {
+            0 | 1 => Err(retryable("busy")),
+            _ => Ok("done".into()),
+        });
+        assert_eq!(call().await, Ok("done".into()));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_after_the_retry_count() {
+        let options = ToolOptions {
+            retries: 2,
+            ..Default::default()
+        };
+        let (call, calls) = guarded(options, |_| Err(retryable("busy")));
+        assert_eq!(call().await, Err(retryable("busy")));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn doubles_the_backoff_between_retries() {
+        let options = ToolOptions {
+            retries: 3,
+            backoff: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&attempts);
+        let (call, _) = guarded(options, move |_| {
+            seen.lock().unwrap().push(Instant::now());
+            Err(retryable("busy"))
+        });
+        call().await.unwrap_err();
+
+        let attempts = attempts.lock().unwrap();
+        let gaps: Vec<_> = attempts.windows(2).map(|w| w[1] - w[0]).collect();
+        assert_eq!(gaps, [100, 200, 400].map(Duration::from_millis).to_vec());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_the_circuit_at_the_threshold() {
+        let (call, calls) = guarded(breaker(3, Duration::from_secs(30)), |n| match n {
+            0 => Err("down".into()),
+            _ => Ok(ToolResult::error("still down").to_json()),
+        });
+        for _ in 0..3 {
+            assert_eq!(failure(&call().await), None);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        assert_eq!(failure(&call().await), Some(ToolFailure::CircuitOpen));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_success_resets_the_failure_count() {
+        let (call, calls) = guarded(breaker(2, Duration::from_secs(30)), |n| match n {
+            1 => Ok("ok".into()),
+            _ => Err("down".into()),
+        });
+        for _ in 0..3 {
+            call().await.unwrap_or_default();
+        }
+        assert_eq!(call().await, Err("down".into()));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closes_the_circuit_after_the_cooldown() {
+        let cooldown = Duration::from_secs(30);
+        let (call, calls) = guarded(breaker(2, cooldown), |n| match n {
+            0..=2 => Err("down".into()),
+            _ => Ok("ok".into()),
+        });
+        call().await.unwrap_err();
+        call().await.unwrap_err();
+        tokio::time::advance(cooldown - Duration::from_secs(1)).await;
+        assert_eq!(failure(&call().await), Some(ToolFailure::CircuitOpen));
+
+        // A failure once the cooldown passes reopens the circuit at once.
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert_eq!(call().await, Err("down".into()));
+        assert_eq!(failure(&call().await), Some(ToolFailure::CircuitOpen));
+
+        // The first success closes it.
+        tokio::time::advance(cooldown + Duration::from_secs(1)).await;
+        assert_eq!(call().await, Ok("ok".into()));
+        assert_eq!(call().await, Ok("ok".into()));
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn error_results_count_as_failures() {
+        assert!(is_error_result(&ToolResult::error("boom").to_json()));
+        assert!(!is_error_result(&ToolResult::success().to_json()));
+        assert!(!is_error_result(r#"{"status": "error"}"#));
+        assert!(!is_error_result("plain output"));
+    }
+
+    #[test]
+    fn strips_the_retryable_mark() {
+        assert_eq!(strip_retryable(retryable("rate limited")), "rate limited");
+        assert_eq!(strip_retryable("other".into()), "other");
+    }
+}
@@ -0,0 +1,285 @@
+//! The collected result of [`Agent::invoke`](crate::Agent::invoke).
+//!
+//! [`Collector`] folds the stream events of one invocation into an
+//! [`AgentResult`]. Tool calls are keyed by tool-use ID since the guest can
+//! report the same tool use more than once (content block start and block).
+//! Interrupts are kept until they are answered.
+
+use serde_json::Value;
+
+use crate::{
+    Interrupt, LifecycleEvent, LifecycleEventType, Message, Metrics, StopData, StopReason,
+    StreamEvent, ToolResult, Usage,
+};
+
+/// The collected result of an agent invocation.
+#[derive(Debug, Clone)]
+pub struct AgentResult {
+    /// The full text produced by the agent.
+    pub text: String,
+    /// Why the agent stopped.
+    pub stop_reason: StopReason,
+    /// Optional token usage information.
+    pub usage: Option<Usage>,
+    /// Optional latency metrics.
+    pub metrics: Option<Metrics>,
+    /// Interrupts left unanswered. When non-empty the invocation stopped early;
+    /// continue it with [`Agent::resume`](crate::Agent::resume).
+    pub interrupts: Vec<Interrupt>,
+    /// Every tool call, in the order the model requested them.
+    pub tool_calls: Vec<ToolCall>,
+    /// Usage and metrics reported by each model call.
+    pub model_calls: Vec<ModelCall>,
+    /// Usage summed across all model calls.
+    pub total_usage: Usage,
+    /// The final assistant message. `None` if the invocation was interrupted
+    /// or did not finish with an assistant turn.
+    pub message: Option<Message>,
+    /// Number of event-loop cycles, i.e. model calls.
+    pub cycles: usize,
+    /// Lifecycle events, in the order the guest raised them.
+    pub lifecycle: Vec<LifecycleEvent>,
+}
+
+impl AgentResult {
+    /// Whether the invocation stopped on unanswered interrupts.
+    pub fn is_interrupted(&self) -> bool {
+        !self.interrupts.is_empty()
+    }
+}
+
+/// A tool call made during an invocation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub name: String,
+    pub tool_use_id: String,
+    pub input: Value,
+    /// The result sent back to the model. `None` if the tool never finished.
+    pub result: Option<ToolResult>,
+}
+
+/// Usage and metrics of a single model call.
+#[derive(Debug, Clone)]
+pub struct ModelCall {
+    pub usage: Option<Usage>,
+    pub metrics: Option<Metrics>,
+}
+
+/// Accumulates stream events into an [`AgentResult`].
+#[derive(Clone, Default)]
+pub(crate) struct Collector {
+    text: String,
+    stop: Option<StopData>,
+    tool_calls: Vec<ToolCall>,
+    model_calls: Vec<ModelCall>,
+    cycles: usize,
+    interrupts: Vec<Interrupt>,
+    lifecycle: Vec<LifecycleEvent>,
+}
+
+impl Collector {
+    /// Record an event. Returns the guest error message for `Error` events.
+    pub(crate) fn record(&mut self, event: StreamEvent) -> Option<String> {
+        match event {
+            StreamEvent::TextDelta(delta) => self.text.push_str(&delta),
+            StreamEvent::ToolUse(event) => {
+                let input =
+                    serde_json::from_str(&event.input).unwrap_or(Value::String(event.input));
+                match self.tool_call(&event.tool_use_id) {
+                    Some(call) => call.input = input,
+                    None => self.tool_calls.push(ToolCall {
+                        name: event.name,
+                        tool_use_id: event.tool_use_id,
+                        input,
+                        result: None,
+                    }),
+                }
+            }
+            StreamEvent::ToolResult(event) => {
+                let result = ToolResult::from_event(&event).ok();
+                if let Some(call) = self.tool_call(&event.tool_use_id) {
+                    call.result = result;
+                }
+            }
+            StreamEvent::Metadata(event) => self.model_calls.push(ModelCall {
+                usage: event.usage,
+                metrics: event.metrics,
+            }),
+            StreamEvent::Lifecycle(event) => {
+                if event.event_type == LifecycleEventType::BeforeModelCall {
+                    self.cycles += 1;
+                }
+                self.lifecycle.push(event);
+            }
+            StreamEvent::Stop(data) => self.stop = Some(data),
+            StreamEvent::Error(err) => return Some(err),
+            StreamEvent::Interrupt(payload) => match Interrupt::from_event(&payload) {
+                Ok(interrupt) => self.interrupts.push(interrupt),
+                Err(e) => tracing::warn!(target: "strands::result", "skipping bad interrupt: {e}"),
+            },
+        }
+        None
+    }
+
+    /// Interrupts recorded and not yet answered.
+    pub(crate) fn unanswered(&self) -> &[Interrupt] {
+        &self.interrupts
+    }
+
+    /// Mark an interrupt as answered.
+    pub(crate) fn answered(&mut self, interrupt_id: &str) {
+        self.interrupts
+            .retain(|interrupt| interrupt.id != interrupt_id);
+    }
+
+    fn tool_call(&mut self, tool_use_id: &str) -> Option<&mut ToolCall> {
+        if tool_use_id.is_empty() {
+            return None;
+        }
+        self.tool_calls
+            .iter_mut()
+            .find(|call| call.tool_use_id == tool_use_id)
+    }
+
+    pub(crate) fn finish(self, history: Option<Vec<Message>>) -> AgentResult {
+        let stop = match self.stop {
+            Some(stop) if self.interrupts.is_empty() => stop,
+            _ => StopData {
+                reason: if self.interrupts.is_empty() {
+                    StopReason::EndTurn
+                } else {
+                    StopReason::ToolUse
+                },
+                usage: None,
+                metrics: None,
+            },
+        };
+
+        let mut total_usage = empty_usage();
+        for usage in self
+            .model_calls
+            .iter()
+            .filter_map(|call| call.usage.as_ref())
+        {
+            accumulate(&mut total_usage, usage);
+        }
+
+        let message = history
+            .and_then(|mut messages| messages.pop())
+            .filter(|message| message.role == crate::Role::Assistant);
+
+        AgentResult {
+            text: self.text,
+            stop_reason: stop.reason,
+            usage: stop.usage,
+            metrics: stop.metrics,
+            interrupts: self.interrupts,
+            tool_calls: self.tool_calls,
+            cycles: self.cycles.max(self.model_calls.len()),
+            model_calls: self.model_calls,
+            total_usage,
+            message,
+            lifecycle: self.lifecycle,
+        }
+    }
+}
+
+/// Usage with every count at zero.
+pub(crate) fn empty_usage() -> Usage {
+    Usage {
+        input_tokens: 0,
+        output_tokens: 0,
+        total_tokens: 0,
+        cache_read_input_tokens: None,
+        cache_write_input_tokens: None,
+    }
+}
+
+/// Add `usage` into `total`, saturating at `i32::MAX`.
+pub(crate) fn accumulate(total: &mut Usage, usage: &Usage) {
+    total.input_tokens = total.input_tokens.saturating_add(usage.input_tokens);
+    total.output_tokens = total.output_tokens.saturating_add(usage.output_tokens);
+    total.total_tokens = total.total_tokens.saturating_add(usage.total_tokens);
+    total.cache_read_input_tokens =
+        add(total.cache_read_input_tokens, usage.cache_read_input_tokens);
+    total.cache_write_input_tokens = add(
+        total.cache_write_input_tokens,
+        usage.cache_write_input_tokens,
+    );
+}
+
+fn add(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+    match (a, b) {
+        (None, None) => None,
+        _ => Some(a.unwrap_or(0).saturating_add(b.unwrap_or(0))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LifecycleEvent;
+
+    fn usage(tokens: i32) -> Usage {
+        Usage {
+            input_tokens: tokens,
+            output_tokens: tokens,
+            total_tokens: tokens,
+            cache_read_input_tokens: Some(tokens),
+            cache_write_input_tokens: None,
+        }
+    }
+
+    #[test]
+    fn accumulate_saturates() {
+        let mut total = usage(i32::MAX - 1);
+        accumulate(&mut total, &usage(10));
+        assert_eq!(total.total_tokens, i32::MAX);
+        assert_eq!(total.cache_read_input_tokens, Some(i32::MAX));
+        assert_eq!(total.cache_write_input_tokens, None);
+    }
+
+    #[test]
+    fn records_interrupts_until_answered() {
+        let mut collector = Collector::default();
+        for id in ["a", "b"] {
+            let payload = serde_json::json!({ "id": id, "name": "tool_approval", "reason": {} });
+            collector.record(StreamEvent::Interrupt(payload.to_string()));
+        }
+        collector.answered("a");
+
+        let result = collector.finish(None);
+        assert!(result.is_interrupted());
+        assert_eq!(result.interrupts.len(), 1);
+        assert_eq!(result.interrupts[0].id, "b");
+        assert_eq!(result.stop_reason, StopReason::ToolUse);
+    }
+
+    #[test]
+    fn records_lifecycle_events() {
+        let mut collector = Collector::default();
+        for event_type in [
+            LifecycleEventType::BeforeInvocation,
+            LifecycleEventType::BeforeModelCall,
+            LifecycleEventType::AfterModelCall,
+        ] {
+            collector.record(StreamEvent::Lifecycle(LifecycleEvent {
+                event_type,
+                tool_use: None,
+                tool_result: None,
+            }));
+        }
+
+        let result = collector.finish(None);
+        assert_eq!(result.cycles, 1);
+        let types: Vec<_> = result.lifecycle.iter().map(|e| e.event_type).collect();
+        assert_eq!(
+            types,
+            [
+                LifecycleEventType::BeforeInvocation,
+                LifecycleEventType::BeforeModelCall,
+                LifecycleEventType::AfterModelCall,
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Host-local scratch space.
+//!
+//! [`Scratch`] is a JSON key/value store that lives only in the host process,
+//! separate from the conversation and from the agent's own state, which tools
+//! read through [`ToolContext::state`](crate::ToolContext::state): the
+//! model never sees it, the guest cannot read it, and it is not saved with the
+//! session, so it is lost when the process exits. Application code reads and
+//! writes it through [`Agent::scratch`](crate::Agent::scratch); tool handlers
+//! read it through their [`ToolContext`](crate::ToolContext).
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// Host-local JSON values shared by an agent and its tools, by key.
+///
+/// Clones share the same values.
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::Agent;
+///
+/// let agent = Agent::builder()
+///     .tool_with_context(
+///         "whoami",
+///         "Get the current user's name.",
+///         serde_json::json!({"type": "object"}),
+///         |_input, ctx| ctx.scratch::<String>("user").ok_or_else(|| "no user".into()),
+///     )
+///     .build()
+///     .await?;
+/// agent.scratch().set("user", "ada")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scratch {
+    values: Arc<RwLock<Map<String, Value>>>,
+}
+
+impl Scratch {
+    /// The value under `key`, if it is set and deserializes as `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.get_value(key)
+            .and_then(|value| serde_json::from_value(value).ok())
+    }
+
+    /// The raw value under `key`.
+    pub fn get_value(&self, key: &str) -> Option<Value> {
+        self.values
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+            .cloned()
+    }
+
+    /// Set `key` to `value`, replacing any previous value.
+    pub fn set(&self, key: impl Into<String>, value: impl Serialize) -> serde_json::Result<()> {
+        let value = serde_json::to_value(value)?;
+        self.values
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.into(), value);
+        Ok(())
+    }
+
+    /// Remove `key`, returning its value.
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        self.values
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key)
+    }
+
+    /// The keys that are set.
+    pub fn keys(&self) -> Vec<String> {
+        self.values
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// A copy of every value.
+    pub fn snapshot(&self) -> Map<String, Value> {
+        self.values
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Remove every value.
+    pub fn clear(&self) {
+        self.values
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// A scratch space starting with a copy of these values that no longer shares
+    /// them.
+    pub(crate) fn detach(&self) -> Self {
+        Self {
+            values: Arc::new(RwLock::new(self.snapshot())),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Owned handle to a guest `response-stream`.
+//!
+//! [`ResponseStream`] borrows the [`Agent`] for as long as it lives. Reads are
+//! driven through a boxed future that takes the `&mut Agent` and hands it back
+//! when the read completes, so `cancel` and `respond` can run between events.
+//!
+//! `Drop` cannot await, so a stream that goes out of scope records its handle
+//! on the agent instead. The agent cancels and drops recorded streams before
+//! it starts the next one. A stream dropped while a read is in flight leaves
+//! the instance inside a guest call that can never finish, so the agent
+//! rebuilds the instance from the history it had when the invocation started.
+//!
+//! The guest raises an interrupt and then waits for its answer, so once the
+//! stream has yielded an interrupt it ends early, rather than reading, until
+//! every interrupt it yielded has been answered with [`ResponseStream::respond`].
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+
+use tokio_util::sync::CancellationToken;
+use wasmtime::component::ResourceAny;
+
+use crate::{Agent, Interrupt, InterruptResponse, Result, StreamEvent, deadline};
+
+/// Streams dropped without being closed.
+#[derive(Default)]
+pub(crate) struct Abandoned {
+    /// Each stream's handle, and whether it was still running.
+    pub(crate) streams: Vec<(ResourceAny, bool)>,
+    /// Set when a stream was dropped mid-read; the instance must be rebuilt.
+    pub(crate) mid_call: bool,
+}
+
+pub(crate) type AbandonedStreams = Arc<Mutex<Abandoned>>;
+
+type ReadResult = wasmtime::Result<Option<Vec<StreamEvent>>>;
+type ReadFuture<'a> = Pin<Box<dyn Future<Output = (&'a mut Agent, ReadResult)> + Send + 'a>>;
+
+enum State<'a> {
+    Idle(&'a mut Agent),
+    Reading(ReadFuture<'a>),
+    Empty,
+}
+
+/// A running agent invocation, returned by [`Agent::stream`].
+///
+/// Yields [`StreamEvent`]s until the agent stops. After a
+/// [`StreamEvent::Interrupt`] the stream pauses, yielding `None`, until the
+/// interrupts are answered with [`respond`](Self::respond); polling it again
+/// then continues the invocation. Dropping the stream early cancels the
+/// invocation. Dropping it while a read is in flight, for example
+/// when it loses a `select!`, abandons the guest call: the agent is then
+/// rebuilt with the history it had before the invocation.
+pub struct ResponseStream<'a> {
+    state: State<'a>,
+    handle: Option<ResourceAny>,
+    buffered: VecDeque<Result<StreamEvent>>,
+    finished: bool,
+    /// IDs of interrupts yielded but not yet answered.
+    unanswered: Vec<String>,
+    abandoned: AbandonedStreams,
+    /// The invocation's token, fired without waiting for the agent so tools
+    /// in flight can stop early.
+    cancellation: CancellationToken,
+}
+
+impl<'a> ResponseStream<'a> {
+    pub(crate) fn new(agent: &'a mut Agent, handle: ResourceAny) -> Self {
+        let abandoned = Arc::clone(&agent.abandoned_streams);
+        let cancellation = agent.store.data().invocation.cancellation.clone();
+        Self {
+            state: State::Idle(agent),
+            handle: Some(handle),
+            buffered: VecDeque::new(),
+            finished: false,
+            unanswered: Vec::new(),
+            abandoned,
+            cancellation,
+        }
+    }
+
+    /// Cancel the invocation. Events already read remain available.
+    pub async fn cancel(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.cancellation.cancel();
+        let handle = self.handle.expect("stream handle taken before close");
+        self.agent().await.cancel_stream(handle).await?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Answer an interrupt the stream yielded.
+    pub async fn respond(&mut self, response: InterruptResponse) -> Result<()> {
+        let handle = self.handle.expect("stream handle taken before close");
+        self.agent()
+            .await
+            .respond(handle, &response.to_payload())
+            .await?;
+        self.unanswered.retain(|id| *id != response.interrupt_id);
+        Ok(())
+    }
+
+    /// Cancel the invocation if it is still running and release the guest
+    /// resource now, rather than before the agent's next invocation.
+    pub async fn close(mut self) -> Result<()> {
+        self.cancel().await?;
+        let handle = self
+            .handle
+            .take()
+            .expect("stream handle taken before close");
+        self.agent().await.drop_stream(handle).await
+    }
+
+    /// Give up the guest resource without cancelling it, to read it on
+    /// later. Only called while the stream is paused on interrupts.
+    pub(crate) fn suspend(mut self) -> ResourceAny {
+        self.handle
+            .take()
+            .expect("stream handle taken before close")
+    }
+
+    /// Wait for any in-flight read and return the agent.
+    async fn agent(&mut self) -> &mut Agent {
+        if let State::Reading(read) = std::mem::replace(&mut self.state, State::Empty) {
+            let (agent, result) = read.await;
+            self.state = State::Idle(agent);
+            self.absorb(result);
+        }
+        match &mut self.state {
+            State::Idle(agent) => agent,
+            _ => unreachable!("response stream polled after a read panicked"),
+        }
+    }
+
+    fn absorb(&mut self, result: ReadResult) {
+        match result {
+            Ok(Some(events)) => {
+                for event in &events {
+                    if let StreamEvent::Interrupt(payload) = event
+                        && let Ok(interrupt) = Interrupt::from_event(payload)
+                    {
+                        self.unanswered.push(interrupt.id);
+                    }
+                }
+                self.buffered.extend(events.into_iter().map(Ok));
+            }
+            Ok(None) => self.finished = true,
+            Err(e) => {
+                self.buffered.push_back(Err(e.into()));
+                self.finished = true;
+            }
+        }
+    }
+}
+
+fn read_next(agent: &mut Agent, handle: ResourceAny) -> ReadFuture<'_> {
+    Box::pin(async move {
+        let Agent {
+            store, instance, ..
+        } = &mut *agent;
+        let result = deadline::read_next(store, instance, handle).await;
+        (agent, result)
+    })
+}
+
+impl futures_core::Stream for ResponseStream<'_> {
+    type Item = Result<StreamEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(event) = this.buffered.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            if this.finished || !this.unanswered.is_empty() {
+                return Poll::Ready(None);
+            }
+            match std::mem::replace(&mut this.state, State::Empty) {
+                State::Idle(agent) => {
+                    let handle = this.handle.expect("stream handle taken before close");
+                    this.state = State::Reading(read_next(agent, handle));
+                }
+                State::Reading(mut read) => match read.as_mut().poll(cx) {
+                    Poll::Ready((agent, result)) => {
+                        this.state = State::Idle(agent);
+                        this.absorb(result);
+                    }
+                    Poll::Pending => {
+                        this.state = State::Reading(read);
+                        return Poll::Pending;
+                    }
+                },
+                State::Empty => unreachable!("response stream polled after a read panicked"),
+            }
+        }
+    }
+}
+
+impl Drop for ResponseStream<'_> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            if !self.finished {
+                self.cancellation.cancel();
+            }
+            let mut abandoned = self
+                .abandoned
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if matches!(self.state, State::Reading(_)) {
+                abandoned.mid_call = true;
+            } else {
+                abandoned.streams.push((handle, !self.finished));
+            }
+        }
+    }
+}
+
+impl Agent {
+    /// Give up an invocation stopped on interrupts; its stream is cancelled
+    /// with the abandoned ones.
+    pub(crate) fn abandon_suspended(&mut self) {
+        if let Some(suspended) = self.suspended.take() {
+            self.abandoned_streams
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .streams
+                .push((suspended.handle, true));
+        }
+    }
+
+    /// Cancel and drop streams whose [`ResponseStream`] went out of scope.
+    pub(crate) async fn release_abandoned_streams(&mut self) -> Result<()> {
+        let abandoned = std::mem::take(
+            &mut self
+                .abandoned_streams
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .streams,
+        );
+        for (handle, running) in abandoned {
+            if running {
+                self.cancel_stream(handle).await?;
+            }
+            self.drop_stream(handle).await?;
+        }
+        Ok(())
+    }
+}
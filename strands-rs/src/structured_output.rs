@@ -0,0 +1,360 @@
+//! Structured output — make the model return a value of a Rust type.
+//!
+//! Mirrors the Python wrapper: a synthetic tool whose input schema is the
+//! target type is offered alongside the agent's tools. If the model answers
+//! without calling it, the tool is forced with `tool_choice`. Input that does
+//! not deserialize is sent back to the model with the validation error until
+//! it succeeds or the retry budget runs out.
+
+use std::time::Instant;
+
+use anyhow::Context;
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde::de::DeserializeOwned;
+use wasmtime::component::ResourceAny;
+
+use crate::interrupt::InterruptHandlerFn;
+use crate::{Agent, Error, Interrupt, Result, StopReason, StreamEvent, ToolResult, ToolSpec};
+
+type ValidateFn = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Intercepts calls to the synthetic output tool inside the host.
+pub(crate) struct OutputCapture {
+    pub(crate) tool_name: String,
+    validate: ValidateFn,
+    value: Option<String>,
+    error: Option<String>,
+}
+
+impl OutputCapture {
+    /// Capture calls to the output tool `tool_name`, whose input must
+    /// deserialize into `T`.
+    fn new<T: DeserializeOwned>(tool_name: String) -> Self {
+        Self {
+            tool_name,
+            validate: Box::new(|input: &str| {
+                serde_json::from_str::<T>(input)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }),
+            value: None,
+            error: None,
+        }
+    }
+
+    /// Record the model's tool input if it validates, otherwise keep the error
+    /// so the next prompt can quote it. The first valid input is the output;
+    /// later calls are acknowledged but not kept.
+    pub(crate) fn accept(&mut self, input: &str) -> Result<String, String> {
+        match (self.validate)(input) {
+            Ok(()) => {
+                self.value.get_or_insert_with(|| input.to_string());
+                self.error = None;
+                Ok(ToolResult::success().text(input).to_json())
+            }
+            Err(e) => {
+                let msg = format!("Validation error: {e}");
+                self.error = Some(msg.clone());
+                Err(msg)
+            }
+        }
+    }
+}
+
+impl Agent {
+    /// Invoke the agent and parse its answer into `T`.
+    ///
+    /// The JSON schema of `T` is offered to the model as an output tool. Invalid
+    /// output is re-prompted with the validation error up to
+    /// [`AgentBuilder::structured_output_retries`](crate::AgentBuilder::structured_output_retries) times.
+    /// The [invocation timeout](crate::AgentBuilder::invocation_timeout)
+    /// bounds the whole call, re-prompts included.
+    ///
+    /// Like any other turn, the exchange stays in the conversation history:
+    /// the prompt, any re-prompts, and the model's calls to the output tool
+    /// with their results.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example() -> anyhow::Result<()> {
+    /// use schemars::JsonSchema;
+    /// use serde::Deserialize;
+    /// use strands::Agent;
+    ///
+    /// /// A person mentioned in the text.
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct Person {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    ///
+    /// let mut agent = Agent::builder().build().await?;
+    /// let person: Person = agent
+    ///     .structured_output("John Smith is a 30 year-old software engineer.")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn structured_output<T: DeserializeOwned + JsonSchema>(
+        &mut self,
+        prompt: &str,
+    ) -> Result<T> {
+        let spec = output_tool_spec::<T>();
+        let name = spec.name.clone();
+        self.store.data_mut().structured_output = Some(OutputCapture::new::<T>(name.clone()));
+
+        let result = self.run_structured_output(prompt, spec).await;
+        let capture = self.store.data_mut().structured_output.take();
+        result?;
+
+        match capture {
+            Some(OutputCapture {
+                value: Some(value), ..
+            }) => serde_json::from_str(&value).map_err(|e| Error::StructuredOutput(e.to_string())),
+            Some(OutputCapture {
+                error: Some(error), ..
+            }) => Err(Error::StructuredOutput(error)),
+            _ => Err(Error::StructuredOutput(format!(
+                "model did not call the {name} tool"
+            ))),
+        }
+    }
+
+    async fn run_structured_output(&mut self, prompt: &str, spec: ToolSpec) -> Result<()> {
+        let name = spec.name.clone();
+        let forced = serde_json::json!({ "tool": { "name": name } }).to_string();
+
+        let deadline = self
+            .invocation_timeout
+            .map(|timeout| Instant::now() + timeout);
+
+        let mut tools = self.tools.clone();
+        tools.push(spec.clone());
+        if self.drain_output(prompt, tools, None, deadline).await? == StopReason::MaxTokens {
+            return Err(Error::StructuredOutput("max tokens reached".into()));
+        }
+
+        let mut attempts = 0;
+        while self.captured().is_none() && attempts < self.structured_output_retries {
+            let reason = match self.captured_error() {
+                Some(error) => format!(
+                    "The input you passed to the {name} tool was invalid: {error}. \
+                     Call the {name} tool again with corrected input."
+                ),
+                None => format!(
+                    "You must format the previous response as structured output. \
+                     Call the {name} tool now."
+                ),
+            };
+            let input = serde_json::json!([{ "text": reason }]).to_string();
+            self.drain_output(&input, vec![spec.clone()], Some(forced.clone()), deadline)
+                .await?;
+            attempts += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Run one stream to completion by `deadline`. With a forced `tool_choice`
+    /// the model would keep calling the output tool, so the stream is
+    /// cancelled as soon as a valid value has been captured.
+    async fn drain_output(
+        &mut self,
+        input: &str,
+        tools: Vec<ToolSpec>,
+        tool_choice: Option<String>,
+        deadline: Option<Instant>,
+    ) -> Result<StopReason> {
+        let forced = tool_choice.is_some();
+        let handle = self
+            .start_stream_until(input, Some(tools), tool_choice, deadline)
+            .await?;
+
+        let handler = self.store.data().interrupts.handler.clone();
+        let mut stop_reason = StopReason::EndTurn;
+        let mut failure = None;
+        while let Some(events) = self.next_events(handle).await? {
+            for event in events {
+                match event {
+                    StreamEvent::Stop(data) => stop_reason = data.reason,
+                    StreamEvent::Error(err) => failure = Some(Error::from_guest(err)),
+                    StreamEvent::Interrupt(payload) if failure.is_none() => {
+                        failure = self.answer(handle, &payload, handler.as_ref()).await?;
+                    }
+                    _ => {}
+                }
+            }
+            if failure.is_some() || (forced && self.captured().is_some()) {
+                self.cancel_stream(handle).await?;
+                break;
+            }
+        }
+        self.drop_stream(handle).await?;
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(stop_reason),
+        }
+    }
+
+    /// Answer an interrupt raised while producing output with the interrupt
+    /// handler. Output cannot be resumed, so an interrupt the handler leaves
+    /// unanswered fails the call.
+    async fn answer(
+        &mut self,
+        handle: ResourceAny,
+        payload: &str,
+        handler: Option<&InterruptHandlerFn>,
+    ) -> Result<Option<Error>> {
+        let interrupt = Interrupt::from_event(payload)
+            .context("failed to parse interrupt")
+            .map_err(Error::Runtime)?;
+        let response = match handler {
+            Some(handler) => handler(interrupt.clone()).await,
+            None => None,
+        };
+        Ok(match response {
+            Some(response) => {
+                let payload = interrupt.respond(response).to_payload();
+                self.respond(handle, &payload).await?;
+                None
+            }
+            None => Some(Error::StructuredOutput(format!(
+                "interrupt '{}' was not answered",
+                interrupt.id
+            ))),
+        })
+    }
+
+    fn captured(&self) -> Option<&str> {
+        self.store
+            .data()
+            .structured_output
+            .as_ref()
+            .and_then(|c| c.value.as_deref())
+    }
+
+    fn captured_error(&self) -> Option<String> {
+        self.store
+            .data()
+            .structured_output
+            .as_ref()
+            .and_then(|c| c.error.clone())
+    }
+}
+
+/// Build the synthetic output tool for `T`, inlining subschemas since not every
+/// provider resolves `$ref`.
+fn output_tool_spec<T: JsonSchema>() -> ToolSpec {
+    let schema = SchemaSettings::draft2020_12()
+        .with(|s| s.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>();
+    let name = T::schema_name()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let description = schema
+        .get("description")
+        .and_then(|d| d.as_str())
+        .unwrap_or(&name)
+        .to_string();
+
+    ToolSpec {
+        description: format!(
+            "{description} -- You MUST call this tool to return structured output."
+        ),
+        input_schema: serde_json::to_string(&schema).expect("schemas always serialize"),
+        name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Where someone lives.
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Address {
+        city: String,
+    }
+
+    /// A person mentioned in the text.
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Person {
+        name: String,
+        address: Address,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[schemars(rename = "person.v2<draft>")]
+    #[allow(dead_code)]
+    struct Renamed {
+        name: String,
+    }
+
+    #[test]
+    fn sanitizes_the_tool_name() {
+        assert_eq!(output_tool_spec::<Person>().name, "Person");
+        let spec = output_tool_spec::<Renamed>();
+        assert_eq!(spec.name, "person_v2_draft_");
+        assert_eq!(
+            spec.description,
+            "person_v2_draft_ -- You MUST call this tool to return structured output."
+        );
+    }
+
+    #[test]
+    fn inlines_the_schema() {
+        let spec = output_tool_spec::<Person>();
+        assert!(
+            spec.description
+                .starts_with("A person mentioned in the text. -- ")
+        );
+        let schema: Value = serde_json::from_str(&spec.input_schema).unwrap();
+        assert!(!spec.input_schema.contains("$ref"));
+        assert!(schema.get("$defs").is_none());
+        assert_eq!(
+            schema["properties"]["address"]["properties"]["city"]["type"],
+            "string"
+        );
+    }
+
+    #[test]
+    fn accepts_valid_input_and_keeps_the_error_otherwise() {
+        let mut capture = OutputCapture::new::<Person>("Person".into());
+        let error = capture.accept(r#"{"name": "Ada"}"#).unwrap_err();
+        assert!(error.starts_with("Validation error: missing field `address`"));
+        assert_eq!(capture.error.as_deref(), Some(error.as_str()));
+        assert_eq!(capture.value, None);
+
+        let valid = r#"{"name": "Ada", "address": {"city": "London"}}"#;
+        let result: ToolResult = serde_json::from_str(&capture.accept(valid).unwrap()).unwrap();
+        assert_eq!(result, ToolResult::success().text(valid));
+        assert_eq!(capture.value.as_deref(), Some(valid));
+        assert_eq!(capture.error, None);
+    }
+
+    #[test]
+    fn keeps_the_first_valid_capture() {
+        let mut capture = OutputCapture::new::<Person>("Person".into());
+        let first = r#"{"name": "Ada", "address": {"city": "London"}}"#;
+        let second = r#"{"name": "Grace", "address": {"city": "Arlington"}}"#;
+        capture.accept(first).unwrap();
+        capture.accept(second).unwrap();
+        capture.accept("{}").unwrap_err();
+        assert_eq!(capture.value.as_deref(), Some(first));
+    }
+}
@@ -0,0 +1,266 @@
+//! Typed tools — serde input/output with a JSON schema derived from the input type.
+//!
+//! A [`Tool`] is registered with [`AgentBuilder::with_tool`](crate::AgentBuilder::with_tool).
+//! The host deserializes the model's input before calling the tool and serializes
+//! the output as the tool result, so implementations never touch raw JSON.
+//! Tools that need the call's [`ToolContext`] implement
+//! [`Tool::call_with_context`].
+
+use std::future::Future;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::{ToolContext, ToolFuture, ToolHandlerFn, ToolSpec};
+
+/// A tool with typed input and output.
+///
+/// The tool's input schema is generated from [`Tool::Input`]. Input the model
+/// sends that does not match it is reported back to the model as a tool error
+/// without calling [`Tool::call`].
+///
+/// # Example
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+/// use strands::{Agent, Tool};
+///
+/// #[derive(Deserialize, JsonSchema)]
+/// struct WeatherInput {
+///     /// City to look up.
+///     city: String,
+/// }
+///
+/// struct Weather;
+///
+/// impl Tool for Weather {
+///     type Input = WeatherInput;
+///     type Output = String;
+///
+///     fn name(&self) -> &str {
+///         "weather"
+///     }
+///
+///     fn description(&self) -> &str {
+///         "Get the current weather for a city."
+///     }
+///
+///     async fn call(&self, input: WeatherInput) -> Result<String, String> {
+///         Ok(format!("It is sunny in {}.", input.city))
+///     }
+/// }
+///
+/// let agent = Agent::builder().with_tool(Weather).build().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Plain functions can be turned into tools with [`#[strands::tool]`](macro@crate::tool):
+///
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use strands::Agent;
+///
+/// /// Get the current weather for a city.
+/// #[strands::tool]
+/// async fn weather(
+///     /// City to look up.
+///     city: String,
+///     /// Temperature unit, `celsius` or `fahrenheit`.
+///     unit: Option<String>,
+/// ) -> Result<String, String> {
+///     let unit = unit.unwrap_or_else(|| "celsius".into());
+///     Ok(format!("It is 21 degrees {unit} in {city}."))
+/// }
+///
+/// let agent = Agent::builder().with_tool(weather).build().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// An argument of type [`ToolContext`] receives the call's context instead of
+/// model input:
+///
+/// ```no_run
+/// use strands::ToolContext;
+///
+/// /// Greet the current user.
+/// #[strands::tool]
+/// fn greet(ctx: ToolContext) -> Result<String, String> {
+///     let user: String = ctx.scratch("user").ok_or("no user")?;
+///     Ok(format!("Hello, {user}!"))
+/// }
+/// ```
+pub trait Tool: Send + Sync + 'static {
+    /// Deserialized from the model's tool-use input.
+    type Input: DeserializeOwned + JsonSchema + Send;
+    /// Serialized to JSON as the tool result.
+    type Output: Serialize;
+
+    /// The name the model uses to call this tool.
+    fn name(&self) -> &str;
+
+    /// Describes the tool to the model.
+    fn description(&self) -> &str;
+
+    /// Run the tool. `Err` is reported to the model as a tool error.
+    fn call(&self, input: Self::Input)
+    -> impl Future<Output = Result<Self::Output, String>> + Send;
+
+    /// Run the tool for a call the agent is serving. Defaults to
+    /// [`Tool::call`]; override it to use the call's [`ToolContext`].
+    fn call_with_context(
+        &self,
+        input: Self::Input,
+        _context: ToolContext,
+    ) -> impl Future<Output = Result<Self::Output, String>> + Send {
+        self.call(input)
+    }
+}
+
+/// Build the [`ToolSpec`] for a typed tool, with the input schema generated from `T::Input`.
+pub fn tool_spec<T: Tool>(tool: &T) -> ToolSpec {
+    let schema = schemars::schema_for!(T::Input);
+    ToolSpec {
+        name: tool.name().to_string(),
+        description: tool.description().to_string(),
+        input_schema: serde_json::to_string(&schema).expect("schemas always serialize"),
+    }
+}
+
+/// Wrap a typed tool in a JSON-in, JSON-out handler.
+pub(crate) fn into_handler<T: Tool>(tool: T) -> ToolHandlerFn {
+    let tool = Arc::new(tool);
+    Arc::new(move |input: &str, context: &ToolContext| -> ToolFuture {
+        let tool = Arc::clone(&tool);
+        let input = serde_json::from_str::<T::Input>(input);
+        let context = context.clone();
+        Box::pin(async move {
+            let input =
+                input.map_err(|e| format!("invalid input for tool '{}': {e}", tool.name()))?;
+            let output = tool.call_with_context(input, context).await?;
+            serde_json::to_string(&output)
+                .map_err(|e| format!("failed to serialize output of tool '{}': {e}", tool.name()))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::HostState;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct AddInput {
+        /// The left operand.
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Serialize)]
+    struct Sum {
+        sum: i64,
+    }
+
+    /// Adds, counting its calls.
+    #[derive(Default)]
+    struct Add {
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Tool for Add {
+        type Input = AddInput;
+        type Output = Sum;
+
+        fn name(&self) -> &str {
+            "add"
+        }
+
+        fn description(&self) -> &str {
+            "Add two numbers."
+        }
+
+        async fn call(&self, input: AddInput) -> Result<Sum, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Sum {
+                sum: input.a + input.b,
+            })
+        }
+    }
+
+    /// Returns output JSON cannot represent.
+    struct Unserializable;
+
+    impl Tool for Unserializable {
+        type Input = ();
+        type Output = HashMap<(u8, u8), u8>;
+
+        fn name(&self) -> &str {
+            "pairs"
+        }
+
+        fn description(&self) -> &str {
+            "Map pairs."
+        }
+
+        async fn call(&self, _input: ()) -> Result<Self::Output, String> {
+            Ok(HashMap::from([((1, 2), 3)]))
+        }
+    }
+
+    fn context() -> ToolContext {
+        HostState::new().tool_context("call", None)
+    }
+
+    #[tokio::test]
+    async fn serializes_the_output() {
+        let handler = into_handler(Add::default());
+        let output = handler(r#"{"a": 2, "b": 3}"#, &context()).await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({"sum": 5})
+        );
+
+        let handler = into_handler(Unserializable);
+        let error = handler("null", &context()).await.unwrap_err();
+        assert!(error.starts_with("failed to serialize output of tool 'pairs'"));
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_input_without_calling_the_tool() {
+        let add = Add::default();
+        let calls = Arc::clone(&add.calls);
+        let handler = into_handler(add);
+        for input in [r#"{"a": 2}"#, r#"{"a": "2", "b": 3}"#, "not json"] {
+            let error = handler(input, &context()).await.unwrap_err();
+            assert!(error.starts_with("invalid input for tool 'add'"), "{error}");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn spec_carries_the_schemars_schema() {
+        let spec = tool_spec(&Add::default());
+        assert_eq!(spec.name, "add");
+        assert_eq!(spec.description, "Add two numbers.");
+        let schema: Value = serde_json::from_str(&spec.input_schema).unwrap();
+        assert_eq!(
+            schema,
+            serde_json::to_value(schemars::schema_for!(AddInput)).unwrap()
+        );
+        assert_eq!(
+            schema["properties"]["a"]["description"],
+            "The left operand."
+        );
+        assert_eq!(schema["required"], json!(["a", "b"]));
+    }
+}
@@ -26,10 +26,7 @@ impl JvmCallbackRelay {
         Self { tx }
     }
 
-    fn call<T: Send + 'static>(
-        &self,
-        f: impl FnOnce() -> T + Send + 'static,
-    ) -> Result<T, String> {
+    fn call<T: Send + 'static>(&self, f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
         let (result_tx, result_rx) = std::sync::mpsc::channel();
         self.tx
             .send(Box::new(move || {
@@ -49,6 +46,32 @@ pub enum AgentError {
         reason: String,
         detail: Option<String>,
     },
+    #[error("wasm trap: {reason}")]
+    Trap { reason: String },
+    #[error("guest exception: {reason}")]
+    Guest { reason: String },
+    #[error("model error{}: {reason}", status.map(|s| format!(" (HTTP {s})")).unwrap_or_default())]
+    Model {
+        reason: String,
+        status: Option<u16>,
+        retryable: bool,
+    },
+    #[error("unknown tool: {name}")]
+    UnknownTool { name: String },
+    #[error("cancelled")]
+    Cancelled,
+    #[error("missing credentials: {reason}")]
+    MissingCredentials { reason: String },
+    #[error("structured output failed: {reason}")]
+    StructuredOutput { reason: String },
+    #[error("failed to load component: {reason}")]
+    ComponentLoad { reason: String },
+    #[error("invocation timed out")]
+    Timeout,
+    #[error("{limit} limit exceeded")]
+    LimitExceeded { limit: String },
+    #[error("multi-agent error: {reason}")]
+    MultiAgent { reason: String },
 }
 
 impl From<String> for AgentError {
@@ -60,15 +83,40 @@ impl From<String> for AgentError {
     }
 }
 
-impl From<anyhow::Error> for AgentError {
-    fn from(e: anyhow::Error) -> Self {
-        let full = e.to_string();
-        let reason = e
-            .chain()
-            .last()
-            .map_or_else(|| full.clone(), |c| c.to_string());
-        let detail = if reason != full { Some(full) } else { None };
-        Self::Runtime { reason, detail }
+impl From<crate::Error> for AgentError {
+    fn from(e: crate::Error) -> Self {
+        match e {
+            crate::Error::Trap { message, .. } => Self::Trap { reason: message },
+            crate::Error::Guest(reason) => Self::Guest { reason },
+            crate::Error::Model {
+                message,
+                status,
+                retryable,
+            } => Self::Model {
+                reason: message,
+                status,
+                retryable,
+            },
+            crate::Error::UnknownTool(name) => Self::UnknownTool { name },
+            crate::Error::Cancelled => Self::Cancelled,
+            crate::Error::MissingCredentials(reason) => Self::MissingCredentials { reason },
+            crate::Error::StructuredOutput(reason) => Self::StructuredOutput { reason },
+            crate::Error::ComponentLoad(reason) => Self::ComponentLoad { reason },
+            crate::Error::Timeout => Self::Timeout,
+            crate::Error::LimitExceeded(limit) => Self::LimitExceeded {
+                limit: limit.to_string(),
+            },
+            crate::Error::MultiAgent(reason) => Self::MultiAgent { reason },
+            crate::Error::Runtime(e) => {
+                let full = e.to_string();
+                let reason = e
+                    .chain()
+                    .last()
+                    .map_or_else(|| full.clone(), |c| c.to_string());
+                let detail = if reason != full { Some(full) } else { None };
+                Self::Runtime { reason, detail }
+            }
+        }
     }
 }
 
@@ -299,18 +347,13 @@ impl Agent {
     ) -> Result<Option<Vec<StreamEvent_>>, AgentError> {
         let mut agent = self.inner.lock().await;
         match agent.next_events(stream.handle).await {
-            Ok(Some(events)) => Ok(Some(
-                events.into_iter().map(StreamEvent_::from).collect(),
-            )),
+            Ok(Some(events)) => Ok(Some(events.into_iter().map(StreamEvent_::from).collect())),
             Ok(None) => Ok(None),
             Err(e) => Err(AgentError::from(e)),
         }
     }
 
-    pub async fn close_stream(
-        self: Arc<Self>,
-        stream: &StreamHandle,
-    ) -> Result<(), AgentError> {
+    pub async fn close_stream(self: Arc<Self>, stream: &StreamHandle) -> Result<(), AgentError> {
         let mut agent = self.inner.lock().await;
         match agent.drop_stream(stream.handle).await {
             Ok(()) => {
@@ -354,3 +397,41 @@ fn build_model_config(input: &ModelConfigInput) -> Result<crate::ModelConfig, Ag
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_errors_keep_status_and_retryable() {
+        let error = AgentError::from(crate::Error::Model {
+            message: "Too many requests".into(),
+            status: Some(429),
+            retryable: true,
+        });
+        assert_eq!(
+            error.to_string(),
+            "model error (HTTP 429): Too many requests"
+        );
+        assert!(matches!(
+            error,
+            AgentError::Model {
+                status: Some(429),
+                retryable: true,
+                ..
+            }
+        ));
+
+        let error = AgentError::from(crate::Error::from_guest(
+            "ValidationException: bad request".into(),
+        ));
+        assert!(matches!(
+            error,
+            AgentError::Model {
+                status: None,
+                retryable: false,
+                ..
+            }
+        ));
+    }
+}
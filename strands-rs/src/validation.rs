@@ -0,0 +1,181 @@
+//! Host-side validation of tool inputs.
+//!
+//! Each tool's input schema is compiled once when the agent is built. A call
+//! whose input does not satisfy its schema never reaches the handler; the
+//! model receives an error listing every violation by JSON pointer so it can
+//! correct the call.
+
+use std::collections::HashMap;
+
+use jsonschema::Validator;
+use serde_json::{Value, json};
+
+use crate::ToolSpec;
+
+/// Compiled input schemas, by tool name.
+#[derive(Clone, Default)]
+pub(crate) struct InputValidators {
+    validators: HashMap<String, Validator>,
+}
+
+impl InputValidators {
+    /// Compile the schema of each tool. A schema that does not compile is
+    /// logged and its tool's inputs are passed through unchecked.
+    pub(crate) fn new(specs: &[ToolSpec]) -> Self {
+        let mut validators = Self {
+            validators: HashMap::with_capacity(specs.len()),
+        };
+        for spec in specs {
+            validators.insert(spec);
+        }
+        validators
+    }
+
+    /// Compile the schema of a tool, replacing any the tool had before.
+    pub(crate) fn insert(&mut self, spec: &ToolSpec) {
+        let compiled = serde_json::from_str::<Value>(&spec.input_schema)
+            .map_err(|e| e.to_string())
+            .and_then(|schema| jsonschema::validator_for(&schema).map_err(|e| e.to_string()));
+        match compiled {
+            Ok(validator) => {
+                self.validators.insert(spec.name.clone(), validator);
+            }
+            Err(e) => {
+                self.validators.remove(&spec.name);
+                tracing::warn!(
+                    target: "strands::tools",
+                    "not validating inputs of '{}', bad input schema: {e}",
+                    spec.name
+                );
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, tool: &str) {
+        self.validators.remove(tool);
+    }
+
+    /// Check a call's input against its tool's schema. `Err` is reported to
+    /// the model as the tool result.
+    pub(crate) fn validate(&self, tool: &str, input: &str) -> Result<(), String> {
+        let Some(validator) = self.validators.get(tool) else {
+            return Ok(());
+        };
+        let violations: Vec<Value> = match serde_json::from_str::<Value>(input) {
+            Ok(input) => validator
+                .iter_errors(&input)
+                .map(|error| json!({ "path": error.instance_path().as_str(), "message": error.to_string() }))
+                .collect(),
+            Err(e) => vec![json!({ "path": "", "message": format!("input is not valid JSON: {e}") })],
+        };
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(json!({
+            "error": format!("invalid input for tool '{tool}'"),
+            "violations": violations,
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, input_schema: &str) -> ToolSpec {
+        ToolSpec {
+            name: name.into(),
+            description: String::new(),
+            input_schema: input_schema.into(),
+        }
+    }
+
+    const POINT: &str = r#"{
+        "type": "object",
+        "properties": {
+            "x": {"type": "integer"},
+            "tags": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": ["x"]
+    }"#;
+
+    fn violations(error: &str) -> Vec<(String, String)> {
+        let error: Value = serde_json::from_str(error).unwrap();
+        error["violations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| {
+                let field = |key: &str| v[key].as_str().unwrap().to_string();
+                (field("path"), field("message"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_every_violation_by_json_pointer() {
+        let validators = InputValidators::new(&[spec("point", POINT)]);
+        assert!(
+            validators
+                .validate("point", r#"{"x": 1, "tags": ["a"]}"#)
+                .is_ok()
+        );
+
+        let error = validators
+            .validate("point", r#"{"x": "one", "tags": ["a", 2]}"#)
+            .unwrap_err();
+        assert!(error.contains("invalid input for tool 'point'"));
+        let mut paths: Vec<_> = violations(&error)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["/tags/1", "/x"]);
+
+        let missing = violations(&validators.validate("point", "{}").unwrap_err());
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].0, "");
+        assert!(missing[0].1.contains("\"x\""));
+    }
+
+    #[test]
+    fn reports_input_that_is_not_json() {
+        let validators = InputValidators::new(&[spec("point", POINT)]);
+        let error = validators.validate("point", "{x: 1").unwrap_err();
+        let violations = violations(&error);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, "");
+        assert!(violations[0].1.starts_with("input is not valid JSON"));
+    }
+
+    #[test]
+    fn passes_through_tools_without_a_usable_schema() {
+        let validators = InputValidators::new(&[
+            spec("unparsable", "{not a schema"),
+            spec("uncompilable", r#"{"type": 5}"#),
+        ]);
+        for tool in ["unknown", "unparsable", "uncompilable"] {
+            assert!(validators.validate(tool, "not even json").is_ok());
+        }
+    }
+
+    #[test]
+    fn insert_replaces_and_remove_forgets_a_schema() {
+        let mut validators = InputValidators::default();
+        validators.insert(&spec("point", POINT));
+        assert!(validators.validate("point", "{}").is_err());
+
+        validators.insert(&spec("point", r#"{"type": "object"}"#));
+        assert!(validators.validate("point", "{}").is_ok());
+        assert!(validators.validate("point", "[]").is_err());
+
+        // A schema that no longer compiles drops the old one.
+        validators.insert(&spec("point", r#"{"type": 5}"#));
+        assert!(validators.validate("point", "[]").is_ok());
+
+        validators.insert(&spec("point", POINT));
+        validators.remove("point");
+        assert!(validators.validate("point", "{}").is_ok());
+    }
+}
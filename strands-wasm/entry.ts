@@ -11,22 +11,39 @@
 
 /// <reference path="./generated/interfaces/strands-agent-types.d.ts" />
 /// <reference path="./generated/interfaces/strands-agent-host-log.d.ts" />
+/// <reference path="./generated/interfaces/strands-agent-host-hooks.d.ts" />
 
 import type {
   AgentConfig,
+  LifecycleEventType,
   StreamEvent,
   StreamArgs,
   RespondArgs,
   SetMessagesArgs,
+  SetToolsArgs,
   ModelConfig,
   ModelParams,
   StopData,
   ToolSpec,
 } from 'strands:agent/types';
 
-import { callTool } from 'strands:agent/tool-provider';
+import { callTool, callTools } from 'strands:agent/tool-provider';
 import { log as hostLog } from 'strands:agent/host-log';
-import { Agent, FunctionTool, SessionManager, FileStorage, S3Storage } from '@strands-agents/sdk';
+import { onEvent as hostHook } from 'strands:agent/host-hooks';
+import type { HookAction } from 'strands:agent/host-hooks';
+import {
+  Agent,
+  FunctionTool,
+  Message,
+  SessionManager,
+  FileStorage,
+  S3Storage,
+  TextBlock,
+  JsonBlock,
+  ToolResultBlock,
+  contentBlockFromData,
+} from '@strands-agents/sdk';
+import { DeleteObjectCommand } from '@aws-sdk/client-s3';
 import { AnthropicModel } from '@strands-agents/sdk/anthropic';
 import { BedrockModel } from '@strands-agents/sdk/bedrock';
 import { OpenAIModel } from '@strands-agents/sdk/openai';
@@ -48,6 +65,19 @@ function errContext(err: unknown, extra?: Record<string, unknown>): Record<strin
   return { error: e.message, stack: e.stack, ...extra };
 }
 
+/**
+ * An error's message with the exception name and HTTP status the host
+ * classifies it by, when the SDK reported them outside the message.
+ */
+function errorMessage(err: any): string {
+  let msg = String(err?.message ?? err);
+  const name = err?.name;
+  if (typeof name === 'string' && name !== 'Error' && !msg.includes(name)) msg = `${name}: ${msg}`;
+  const status = err?.status ?? err?.statusCode ?? err?.$metadata?.httpStatusCode;
+  if (typeof status === 'number' && !msg.includes(String(status))) msg = `${msg} (status code ${status})`;
+  return msg;
+}
+
 function mapUsage(src: any): import('strands:agent/types').Usage | undefined {
   if (src == null) return undefined;
   return {
@@ -82,10 +112,6 @@ function mapStopReason(reason: StopReason, agentResult?: any): StopData {
 }
 
 function mapEvent(event: AgentStreamEvent): StreamEvent | null {
-  if ('interrupt' in event || ('type' in event && (event as any).type === 'interrupt')) {
-    return { tag: 'interrupt', val: JSON.stringify(event) };
-  }
-
   if (!('type' in event)) {
     return null;
   }
@@ -127,12 +153,13 @@ function mapEvent(event: AgentStreamEvent): StreamEvent | null {
   }
 
   if (ev.type === 'toolResultBlock') {
+    const media = pendingToolMedia.get(ev.toolUseId ?? '') ?? [];
     return {
       tag: 'tool-result',
       val: {
         toolUseId: ev.toolUseId ?? '',
         status: ev.status ?? 'success',
-        content: JSON.stringify(ev.content ?? []),
+        content: JSON.stringify([...(ev.content ?? []), ...media.map((m) => m.part)]),
       },
     };
   }
@@ -227,45 +254,131 @@ function createModel(config?: ModelConfig, params?: ModelParams): Model<BaseMode
   }
 }
 
+// The SDK's tool results carry only text and JSON.  Image and document parts
+// from the host wait here, keyed by tool use ID, as both the host's part and
+// the SDK block.  The tool-result stream event reports the parts with the
+// rest of the result, and the blocks are appended to the message that carries
+// the tool's result.
+type ToolMedia = { part: any; block: any };
+const pendingToolMedia = new Map<string, ToolMedia[]>();
+
+/** Convert host result content, setting image and document parts aside. */
+function toolResultContent(toolUseId: string, parts: any[]): (TextBlock | JsonBlock)[] {
+  const content: (TextBlock | JsonBlock)[] = [];
+  const media: ToolMedia[] = [];
+  for (const part of parts) {
+    if ('text' in part) content.push(new TextBlock(part.text));
+    else if ('json' in part) content.push(new JsonBlock(part));
+    else if ('image' in part || 'document' in part) media.push({ part, block: contentBlockFromData(part) });
+    else glog('warn', 'tool result: unsupported content part', { toolUseId, keys: Object.keys(part) });
+  }
+  if (media.length > 0) {
+    pendingToolMedia.set(toolUseId, [...(pendingToolMedia.get(toolUseId) ?? []), ...media]);
+    if (content.length === 0) content.push(new TextBlock(`${media.length} attachment(s) follow.`));
+  }
+  return content;
+}
+
+/** Build a result block from a host `{status, content}` result. */
+function hostToolResult(toolUseId: string, data: any): ToolResultBlock {
+  return new ToolResultBlock({
+    toolUseId,
+    status: data.status === 'error' ? 'error' : 'success',
+    content: toolResultContent(toolUseId, data.content ?? []),
+  });
+}
+
+/** Move a tool result message's pending media into the message. */
+function attachToolMedia(message: Message): void {
+  for (const block of [...message.content]) {
+    if (block.type !== 'toolResultBlock') continue;
+    const media = pendingToolMedia.get(block.toolUseId);
+    if (!media) continue;
+    pendingToolMedia.delete(block.toolUseId);
+    (message.content as any[]).push(...media.map((m) => m.block));
+  }
+}
+
+// Host results built by a HostTool callback, by tool use ID.
+const hostResults = new Map<string, ToolResultBlock>();
+
+/**
+ * A FunctionTool that returns host `{status, content}` results as built,
+ * instead of letting FunctionTool wrap the content array as JSON.
+ */
+class HostTool extends FunctionTool {
+  override async *stream(toolContext: any): AsyncGenerator<any, ToolResultBlock, unknown> {
+    const result = yield* super.stream(toolContext);
+    const toolUseId = toolContext.toolUse.toolUseId;
+    const built = hostResults.get(toolUseId);
+    hostResults.delete(toolUseId);
+    return built ?? result;
+  }
+}
+
+type ToolOutcome = { tag: 'ok'; val: string } | { tag: 'err'; val: string };
+
+// Host outcomes of the current turn's tool calls, by tool use ID.  The SDK
+// runs a turn's tools one at a time, so the bridge sends the whole batch to
+// the host in one callTools call when BeforeToolsEvent fires, letting the
+// host run them concurrently, and each tool's callback picks up its outcome
+// here.
+const batchResults = new Map<string, ToolOutcome>();
+
+/** The agent's state, as sent to the host with its tool calls. */
+function agentStateJson(agent: any): string | undefined {
+  const state = agent?.state?.getAll?.();
+  return state === undefined ? undefined : JSON.stringify(state);
+}
+
+/** Call a single host tool, outside a batch. */
+function callHostTool(name: string, input: unknown, toolUseId: string, agentState?: string): ToolOutcome {
+  try {
+    const result: any = callTool({ name, input: JSON.stringify(input), toolUseId, agentState });
+    return typeof result === 'object' && result !== null && 'tag' in result ? result : { tag: 'ok', val: result };
+  } catch (e: any) {
+    glog('error', 'callTool: host threw', errContext(e, { tool: name }));
+    throw new Error(String(e?.message ?? e));
+  }
+}
+
 function createTools(specs: ToolSpec[] | undefined): FunctionTool[] | undefined {
   if (!specs || specs.length === 0) return undefined;
 
   return specs.map(
     (spec) =>
-      new FunctionTool({
+      new HostTool({
         name: spec.name,
         description: spec.description,
         inputSchema: JSON.parse(spec.inputSchema),
         callback: (input: unknown, toolContext: any) => {
           const toolUseId = toolContext?.toolUse?.toolUseId ?? '';
 
-          let result: any;
-          try {
-            result = callTool({
-              name: spec.name,
-              input: JSON.stringify(input),
-              toolUseId,
-            });
-          } catch (e: any) {
-            glog('error', 'callTool: host threw', errContext(e, { tool: spec.name }));
-            throw new Error(String(e?.message ?? e));
+          const action = pendingToolActions.get(toolUseId);
+          pendingToolActions.delete(toolUseId);
+          if (action?.tag === 'cancel') {
+            glog('info', 'callTool: cancelled by host hook', { tool: spec.name, reason: action.val });
+            throw new Error(action.val);
+          }
+          if (action?.tag === 'replace') {
+            input = JSON.parse(action.val);
           }
 
-          let parsed: any;
-          if (typeof result === 'object' && result !== null && 'tag' in result) {
-            if (result.tag === 'err') {
-              glog('warn', 'callTool: host returned error', { tool: spec.name, error: result.val });
-              throw new Error(result.val);
-            }
-            parsed = JSON.parse(result.val);
-          } else {
-            parsed = JSON.parse(result);
+          // Retried calls, and calls the batch did not include, go alone.
+          const result =
+            batchResults.get(toolUseId) ??
+            callHostTool(spec.name, input, toolUseId, agentStateJson(toolContext?.agent));
+          batchResults.delete(toolUseId);
+          if (result.tag === 'err') {
+            glog('warn', 'callTool: host returned error', { tool: spec.name, error: result.val });
+            throw new Error(result.val);
           }
+          const parsed = JSON.parse(result.val);
 
-          // Return just the content if it's a wrapped tool result.
-          // The TS SDK expects content blocks, not the {status, content} wrapper.
+          // A wrapped tool result is built here and returned by HostTool.stream.
           if (parsed && typeof parsed === 'object' && 'status' in parsed && 'content' in parsed) {
-            return parsed.content;
+            hostResults.set(toolUseId, hostToolResult(toolUseId, parsed));
+            return undefined;
           }
           return parsed;
         },
@@ -302,11 +415,104 @@ import {
   BeforeInvocationEvent,
   BeforeModelCallEvent,
   BeforeToolCallEvent,
+  BeforeToolsEvent,
   MessageAddedEvent,
 } from '@strands-agents/sdk';
 
+// The SDK's tool call events are read-only, so host hook decisions are
+// applied around them: before-tool-call actions wait here, keyed by tool use
+// ID, until the tool's callback runs, and a rewritten result is copied onto
+// the block the agent loop is about to return.
+const pendingToolActions = new Map<string, HookAction>();
+
+/** Forget tool state left by a stream that ended before its tools finished. */
+function clearToolState(): void {
+  pendingToolMedia.clear();
+  hostResults.clear();
+  batchResults.clear();
+  pendingToolActions.clear();
+}
+
+function replaceToolResult(target: ToolResultBlock, json: string): void {
+  const replacement = hostToolResult(target.toolUseId, JSON.parse(json));
+  Object.assign(target as any, { status: replacement.status, content: replacement.content });
+}
+
+type RaisedInterrupt = { id: string; name: string; reason: unknown };
+
+/**
+ * Interrupts raised by host hooks.  A hook that asks for input holds the
+ * agent loop until the host answers through `response-stream.respond`;
+ * meanwhile readNext hands the interrupts to the host instead of waiting on
+ * the loop.  Answers are applied on the next readNext, so the loop only
+ * moves on while the host is reading.
+ */
+class InterruptGate {
+  private waiting = new Map<string, (response: unknown) => void>();
+  private answered: (() => void)[] = [];
+  private raised: RaisedInterrupt[] = [];
+  private wake: (() => void) | null = null;
+  cancelled = false;
+
+  /** Raise interrupts and wait until all of them are answered. */
+  raise(interrupts: RaisedInterrupt[]): Promise<unknown[]> {
+    const answers = interrupts.map((i) => new Promise<unknown>((resolve) => this.waiting.set(i.id, resolve)));
+    this.raised.push(...interrupts);
+    this.wake?.();
+    this.wake = null;
+    return Promise.all(answers);
+  }
+
+  /** Resolves once there are raised interrupts to hand to the host. */
+  raisedSignal(): Promise<void> {
+    if (this.raised.length > 0) return Promise.resolve();
+    return new Promise((resolve) => (this.wake = resolve));
+  }
+
+  /** Whether interrupts handed to the host are still unanswered. */
+  get paused(): boolean {
+    return this.waiting.size > 0 && this.raised.length === 0;
+  }
+
+  drain(): StreamEvent[] {
+    return this.raised.splice(0).map((i) => ({ tag: 'interrupt', val: JSON.stringify(i) }) as StreamEvent);
+  }
+
+  answer(id: string, response: unknown): boolean {
+    const resolve = this.waiting.get(id);
+    if (!resolve) return false;
+    this.waiting.delete(id);
+    this.answered.push(() => resolve(response));
+    return true;
+  }
+
+  /** Hand the answers received since the last read to the loop. */
+  release(): void {
+    for (const resolve of this.answered.splice(0)) resolve();
+  }
+
+  /** Release the loop from every wait; the paused calls are cancelled. */
+  cancel(): void {
+    const waiting = [...this.waiting.values()];
+    this.reset();
+    this.cancelled = true;
+    for (const resolve of waiting) resolve(null);
+  }
+
+  reset(): void {
+    this.waiting.clear();
+    this.answered = [];
+    this.raised = [];
+    this.wake = null;
+    this.cancelled = false;
+  }
+}
+
+type ToolUseData = { name: string; toolUseId: string; input: unknown };
+
 class LifecycleBridge implements HookProvider {
   queue: StreamEvent[] = [];
+  gate = new InterruptGate();
 
   private push(eventType: string, toolUse?: unknown, toolResult?: unknown): void {
     this.queue.push({
@@ -319,23 +525,126 @@ class LifecycleBridge implements HookProvider {
     } as any);
   }
 
+  /** Queue a stream event and run the host's hooks for it. */
+  private notify(eventType: LifecycleEventType): void {
+    this.push(eventType);
+    hostHook({ eventType });
+  }
+
   registerCallbacks(registry: HookRegistry): void {
-    registry.addCallback(InitializedEvent, () => this.push('initialized'));
-    registry.addCallback(BeforeInvocationEvent, () => this.push('before-invocation'));
-    registry.addCallback(AfterInvocationEvent, () => this.push('after-invocation'));
-    registry.addCallback(BeforeModelCallEvent, () => this.push('before-model-call'));
-    registry.addCallback(AfterModelCallEvent, () => this.push('after-model-call'));
-    registry.addCallback(MessageAddedEvent, () => this.push('message-added'));
-
-    registry.addCallback(BeforeToolCallEvent, (event: InstanceType<typeof BeforeToolCallEvent>) => {
+    registry.addCallback(InitializedEvent, () => this.notify('initialized'));
+    registry.addCallback(BeforeInvocationEvent, () => this.notify('before-invocation'));
+    registry.addCallback(AfterInvocationEvent, () => this.notify('after-invocation'));
+    registry.addCallback(BeforeModelCallEvent, () => this.notify('before-model-call'));
+    registry.addCallback(AfterModelCallEvent, () => this.notify('after-model-call'));
+
+    registry.addCallback(MessageAddedEvent, (event: InstanceType<typeof MessageAddedEvent>) => {
+      attachToolMedia(event.message);
+      this.push('message-added');
+      hostHook({ eventType: 'message-added', message: JSON.stringify(event.message) });
+    });
+
+    registry.addCallback(BeforeToolsEvent, async (event: InstanceType<typeof BeforeToolsEvent>) => {
+      await this.dispatchBatch(event.message, event.agent);
+    });
+
+    registry.addCallback(BeforeToolCallEvent, async (event: InstanceType<typeof BeforeToolCallEvent>) => {
       this.push('before-tool-call', event.toolUse);
+      const id = event.toolUse.toolUseId;
+      // Calls in the batch had their hooks run when it was dispatched.
+      if (batchResults.has(id) || pendingToolActions.has(id)) return;
+      const action = (await this.beforeToolCalls([event.toolUse])).get(id)!;
+      if (action.tag !== 'proceed') {
+        pendingToolActions.set(id, action);
+      }
     });
 
     registry.addCallback(AfterToolCallEvent, (event: InstanceType<typeof AfterToolCallEvent>) => {
+      // Not consumed if the tool was missing from the registry.
+      pendingToolActions.delete(event.toolUse.toolUseId);
+      const action = hostHook({
+        eventType: 'after-tool-call',
+        toolUse: JSON.stringify(event.toolUse),
+        toolResult: JSON.stringify(event.result.toJSON().toolResult),
+      });
+      if (action.tag === 'replace') {
+        replaceToolResult(event.result, action.val);
+      }
       this.push('after-tool-call', event.toolUse, event.result as unknown);
     });
   }
 
+  /**
+   * Run the host's before-tool-call hooks for tool uses.  Calls the hooks
+   * pause are raised as interrupts together, and once all are answered the
+   * hooks run again for them with the answers.
+   */
+  private async beforeToolCalls(toolUses: ToolUseData[]): Promise<Map<string, HookAction>> {
+    const actions = new Map<string, HookAction>();
+    const paused: ToolUseData[] = [];
+    const interrupts: RaisedInterrupt[] = [];
+    for (const toolUse of toolUses) {
+      const action = hostHook({ eventType: 'before-tool-call', toolUse: JSON.stringify(toolUse) });
+      actions.set(toolUse.toolUseId, action);
+      if (action.tag === 'interrupt') {
+        const { name, reason } = JSON.parse(action.val);
+        paused.push(toolUse);
+        interrupts.push({ id: `${name}-${toolUse.toolUseId}`, name, reason });
+      }
+    }
+    if (interrupts.length === 0) return actions;
+
+    glog('debug', 'beforeToolCalls: raising interrupts', { count: interrupts.length });
+    const responses = await this.gate.raise(interrupts);
+    paused.forEach((toolUse, i) => {
+      let action: HookAction = { tag: 'cancel', val: 'invocation was cancelled' };
+      if (!this.gate.cancelled) {
+        action = hostHook({
+          eventType: 'before-tool-call',
+          toolUse: JSON.stringify(toolUse),
+          interruptResponse: JSON.stringify(responses[i] ?? null),
+        });
+      }
+      if (action.tag === 'interrupt') action = { tag: 'cancel', val: 'tool call was not approved' };
+      actions.set(toolUse.toolUseId, action);
+    });
+    return actions;
+  }
+
+  /**
+   * Run the before-tool-call hooks for a turn's host tool calls, then send
+   * the calls that were not cancelled to the host in one batch.
+   */
+  private async dispatchBatch(message: Message, agent: any): Promise<void> {
+    const toolUses: ToolUseData[] = [];
+    for (const block of message.content) {
+      if (block.type !== 'toolUseBlock') continue;
+      const tool = agent?.toolRegistry?.find((t: any) => t.name === block.name);
+      if (!(tool instanceof HostTool)) continue;
+      toolUses.push({ name: block.name, toolUseId: block.toolUseId, input: block.input });
+    }
+
+    const actions = await this.beforeToolCalls(toolUses);
+    const agentState = agentStateJson(agent);
+    const calls: import('strands:agent/types').CallToolArgs[] = [];
+    for (const toolUse of toolUses) {
+      const action = actions.get(toolUse.toolUseId)!;
+      if (action.tag !== 'proceed') pendingToolActions.set(toolUse.toolUseId, action);
+      if (action.tag === 'cancel') continue;
+      calls.push({
+        name: toolUse.name,
+        input: action.tag === 'replace' ? action.val : JSON.stringify(toolUse.input),
+        toolUseId: toolUse.toolUseId,
+        agentState,
+      });
+    }
+    if (calls.length === 0) return;
+
+    glog('debug', 'callTools: dispatching batch', { count: calls.length });
+    const results = callTools({ calls });
+    calls.forEach((call, i) => batchResults.set(call.toolUseId, results[i]));
+  }
+
   drain(): StreamEvent[] {
     return this.queue.splice(0);
   }
@@ -439,36 +748,79 @@ class AgentImpl {
   }
 
   setMessages(args: SetMessagesArgs): void {
-    const newMessages = JSON.parse(args.json);
+    const newMessages = JSON.parse(args.json).map((data: any) => Message.fromMessageData(data));
     this.agent.messages.splice(0, this.agent.messages.length, ...newMessages);
   }
 
+  setTools(args: SetToolsArgs): void {
+    glog('debug', 'AgentImpl.setTools', { toolCount: args.tools.length });
+    this.defaultTools = createTools(args.tools);
+    this.agent.toolRegistry.clear();
+    if (this.defaultTools) {
+      this.agent.toolRegistry.addAll(this.defaultTools);
+    }
+  }
+
   async saveSession(): Promise<void> {
     if (!this.sessionManager) throw new Error('No session manager configured');
     await this.sessionManager.saveSnapshot({ target: this.agent, isLatest: true });
   }
 
-  async listSnapshots(): Promise<string[]> {
+  /** The storage and location of this agent's snapshots. */
+  private snapshotScope(): { storage: any; location: any } {
     if (!this.sessionManager) throw new Error('No session manager configured');
-    const storage = (this.sessionManager as any)._storage.snapshot;
-    const location = (this.sessionManager as any)._location?.(this.agent)
-      ?? { sessionId: (this.sessionManager as any)._sessionId, scope: 'agent', scopeId: this.agent.agentId };
+    const manager = this.sessionManager as any;
+    const location = manager._location?.(this.agent)
+      ?? { sessionId: manager._sessionId, scope: 'agent', scopeId: this.agent.agentId };
+    return { storage: manager._storage.snapshot, location };
+  }
+
+  async listSnapshots(): Promise<string[]> {
+    const { storage, location } = this.snapshotScope();
     return storage.listSnapshotIds({ location });
   }
 
   async deleteSession(): Promise<void> {
-    if (!this.sessionManager) throw new Error('No session manager configured');
-    // Delete by removing all snapshots - FileStorage/S3Storage don't have a bulk delete,
-    // so we'd need to implement this per-storage. For now, list and delete individually.
-    // TODO: Add deleteSession to SnapshotStorage interface upstream.
-    throw new Error('deleteSession not yet implemented');
+    const { storage, location } = this.snapshotScope();
+    const ids: string[] = await storage.listSnapshotIds({ location });
+    glog('info', 'AgentImpl.deleteSession', { sessionId: location.sessionId, snapshots: ids.length });
+    await deleteSnapshots(storage, location, ids);
+  }
+}
+
+// SnapshotStorage has no delete, so a session's objects are removed through
+// the built-in backends' own paths: each history snapshot, then the latest
+// snapshot and the manifest.
+async function deleteSnapshots(storage: any, location: any, ids: string[]): Promise<void> {
+  if (storage instanceof FileStorage) {
+    const { promises: fs } = await import('fs');
+    const paths = [
+      ...(await Promise.all(ids.map((id) => storage._getHistorySnapshotPath(location, id)))),
+      await storage._getLatestSnapshotPath(location),
+      await storage._getPath(location, 'manifest.json'),
+    ];
+    for (const path of paths) await fs.rm(path, { force: true });
+    return;
+  }
+  if (storage instanceof S3Storage) {
+    const keys = [
+      ...ids.map((id) => storage._getHistorySnapshotKey(location, id)),
+      storage._getLatestSnapshotKey(location),
+      storage._getKey(location, 'manifest.json'),
+    ];
+    for (const key of keys) {
+      await storage._s3.send(new DeleteObjectCommand({ Bucket: storage._bucket, Key: key }));
+    }
+    return;
   }
+  throw new Error('deleteSession: unsupported snapshot storage');
 }
 
 class ResponseStreamImpl {
   private done = false;
   private generator: AsyncGenerator<AgentStreamEvent, any, undefined>;
-  private interruptResolve: ((payload: string) => void) | null = null;
+  // A read of the loop left waiting while interrupts were handed to the host.
+  private pendingNext: Promise<IteratorResult<AgentStreamEvent, any>> | null = null;
   private agent: Agent;
   private bridge: LifecycleBridge;
   private defaultTools: FunctionTool[] | undefined;
@@ -480,10 +832,12 @@ class ResponseStreamImpl {
     this.bridge = bridge;
     this.defaultTools = defaultTools;
     this.originalModel = originalModel;
+    bridge.gate.reset();
     this.generator = agent.stream(parseInput(input) as any);
   }
 
   private restoreDefaults(): void {
+    clearToolState();
     if (this.originalModel) {
       (this.agent as any).model = this.originalModel;
     }
@@ -495,10 +849,18 @@ class ResponseStreamImpl {
 
   async readNext(): Promise<StreamEvent[] | undefined> {
     if (this.done) return undefined;
+    const gate = this.bridge.gate;
+    gate.release();
+    if (gate.paused) return [];
 
     try {
-      const result = await this.generator.next();
-      const lifecycle = this.bridge.drain();
+      this.pendingNext ??= this.generator.next();
+      const result = await Promise.race([this.pendingNext, gate.raisedSignal().then(() => null)]);
+      if (result === null) {
+        return [...this.bridge.drain(), ...gate.drain()];
+      }
+      this.pendingNext = null;
+      const lifecycle = [...this.bridge.drain(), ...gate.drain()];
 
       if (result.done) {
         this.done = true;
@@ -518,21 +880,23 @@ class ResponseStreamImpl {
       this.done = true;
       this.restoreDefaults();
       const lifecycle = this.bridge.drain();
-      const msg = String(err?.message ?? err);
-      return [...lifecycle, { tag: 'error', val: msg }];
+      return [...lifecycle, { tag: 'error', val: errorMessage(err) }];
     }
   }
 
   respond(args: RespondArgs): void {
-    if (this.interruptResolve) {
-      this.interruptResolve(args.payload);
-      this.interruptResolve = null;
+    const { interruptId, response } = JSON.parse(args.payload);
+    if (!this.bridge.gate.answer(interruptId, response)) {
+      glog('warn', 'respond: no pending interrupt', { interruptId });
     }
   }
 
   cancel(): void {
+    if (this.done) return;
     this.done = true;
+    this.bridge.gate.cancel();
     this.generator.return(undefined);
+    this.restoreDefaults();
   }
 }
 
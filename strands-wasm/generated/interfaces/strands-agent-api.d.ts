@@ -7,12 +7,14 @@ declare module 'strands:agent/api' {
   export type StreamArgs = import('strands:agent/types').StreamArgs;
   export type RespondArgs = import('strands:agent/types').RespondArgs;
   export type SetMessagesArgs = import('strands:agent/types').SetMessagesArgs;
+  export type SetToolsArgs = import('strands:agent/types').SetToolsArgs;
   
   export class Agent {
     constructor(config: AgentConfig)
     generate(args: StreamArgs): ResponseStream;
     getMessages(): string;
     setMessages(args: SetMessagesArgs): void;
+    setTools(args: SetToolsArgs): void;
     saveSession(): void;
     listSnapshots(): Array<string>;
     deleteSession(): void;
@@ -0,0 +1,11 @@
+// @generated from wit/agent.wit -- do not edit
+
+/// <reference path="./strands-agent-types.d.ts" />
+declare module 'strands:agent/host-hooks' {
+  /**
+   * Run the host's hooks for `event`.
+   */
+  export function onEvent(event: HookEvent): HookAction;
+  export type HookEvent = import('strands:agent/types').HookEvent;
+  export type HookAction = import('strands:agent/types').HookAction;
+}
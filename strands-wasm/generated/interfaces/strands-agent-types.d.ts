@@ -107,6 +107,10 @@ declare module 'strands:agent/types' {
     tag: 'error',
     val: string,
   }
+  /**
+   * JSON `{id, name, reason}`.  The stream waits until the interrupt is
+   * answered through `response-stream.respond`.
+   */
   export interface StreamEventInterrupt {
     tag: 'interrupt',
     val: string,
@@ -195,19 +199,81 @@ declare module 'strands:agent/types' {
     name: string,
     input: string,
     toolUseId: string,
+    /**
+     * JSON object of the agent's state when the call was made.
+     */
+    agentState?: string,
   }
   export interface CallToolsArgs {
     calls: Array<CallToolArgs>,
   }
+  export interface HookEvent {
+    eventType: LifecycleEventType,
+    /**
+     * JSON tool use (`name`, `toolUseId`, `input`), for tool events.
+     */
+    toolUse?: string,
+    /**
+     * JSON tool result (`toolUseId`, `status`, `content`), for
+     * after-tool-call.
+     */
+    toolResult?: string,
+    /**
+     * JSON message, for message-added.
+     */
+    message?: string,
+    /**
+     * JSON answer to the interrupt an earlier before-tool-call for this
+     * tool use returned.
+     */
+    interruptResponse?: string,
+  }
+  export type HookAction = HookActionProceed | HookActionCancel | HookActionReplace | HookActionInterrupt;
+  /**
+   * Continue unchanged.
+   */
+  export interface HookActionProceed {
+    tag: 'proceed',
+  }
+  /**
+   * Skip the tool call.  The model sees an error result with this reason.
+   */
+  export interface HookActionCancel {
+    tag: 'cancel',
+    val: string,
+  }
+  /**
+   * Replacement JSON: the tool input for before-tool-call, the tool
+   * result for after-tool-call.
+   */
+  export interface HookActionReplace {
+    tag: 'replace',
+    val: string,
+  }
+  /**
+   * Pause before-tool-call for input: JSON `{name, reason}`.  The guest
+   * raises it as a stream interrupt and runs the hooks again with the
+   * answer in `interrupt-response`.
+   */
+  export interface HookActionInterrupt {
+    tag: 'interrupt',
+    val: string,
+  }
   export interface StreamArgs {
     input: string,
     tools?: Array<ToolSpec>,
     toolChoice?: string,
   }
   export interface RespondArgs {
+    /**
+     * JSON `{interruptId, response}`.
+     */
     payload: string,
   }
   export interface SetMessagesArgs {
     json: string,
   }
+  export interface SetToolsArgs {
+    tools: Array<ToolSpec>,
+  }
 }
@@ -1,10 +1,12 @@
 // @generated from wit/agent.wit -- do not edit
 
 /// <reference path="./interfaces/strands-agent-api.d.ts" />
+/// <reference path="./interfaces/strands-agent-host-hooks.d.ts" />
 /// <reference path="./interfaces/strands-agent-host-log.d.ts" />
 /// <reference path="./interfaces/strands-agent-tool-provider.d.ts" />
 /// <reference path="./interfaces/strands-agent-types.d.ts" />
 declare module 'strands:agent/agent' {
+  export type * as StrandsAgentHostHooks from 'strands:agent/host-hooks'; // import strands:agent/host-hooks
   export type * as StrandsAgentHostLog from 'strands:agent/host-log'; // import strands:agent/host-log
   export type * as StrandsAgentToolProvider from 'strands:agent/tool-provider'; // import strands:agent/tool-provider
   export type * as StrandsAgentTypes from 'strands:agent/types'; // import strands:agent/types